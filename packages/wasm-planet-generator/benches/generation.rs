@@ -0,0 +1,104 @@
+//! Benchmarks of the generation pipeline, run natively with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+use wasm_planet_generator::climate::ClimateOptions;
+use wasm_planet_generator::coastline::CoastlineOptions;
+use wasm_planet_generator::elevation::elevate;
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::points::{generate_points, perturb_points};
+use wasm_planet_generator::tectonics::TectonicsOptions;
+
+const SEED: u64 = 42;
+const CHAOS: f64 = 0.7;
+
+// (width, height, spacing) of the benchmarked maps.
+const SIZES: [(f64, f64, f64); 4] = [
+  (400.0, 300.0, 8.0),
+  (400.0, 300.0, 4.0),
+  (800.0, 600.0, 8.0),
+  (800.0, 600.0, 4.0)
+];
+
+fn label(size: &(f64, f64, f64)) -> String {
+  format!("{}x{}@{}", size.0, size.1, size.2)
+}
+
+fn all_stages() -> Options {
+  Options {
+    relaxation: 1,
+    tectonics: Some(TectonicsOptions::default()),
+    target_land_ratio: Some(0.3),
+    coastlines: Some(CoastlineOptions { smoothing: 2 }),
+    hydrology: Some(HydrologyOptions::default()),
+    climate: Some(ClimateOptions::default()),
+    distances: true,
+    ..Options::default()
+  }
+}
+
+fn bench_points(c: &mut Criterion) {
+  let mut group = c.benchmark_group("generate_points");
+  for size in SIZES.iter() {
+    group.bench_with_input(BenchmarkId::from_parameter(label(size)), size, |b, (width, height, spacing)| {
+      b.iter(|| generate_points(*width, *height, *spacing))
+    });
+  }
+  group.finish();
+
+  let mut group = c.benchmark_group("perturb_points");
+  for size in SIZES.iter() {
+    let (width, height, spacing) = *size;
+    let points = generate_points(width, height, spacing);
+    group.bench_function(BenchmarkId::from_parameter(label(size)), |b| {
+      b.iter_batched(|| points.clone(), |points| perturb_points(SEED, points, spacing, CHAOS), BatchSize::LargeInput)
+    });
+  }
+  group.finish();
+}
+
+fn bench_elevate(c: &mut Criterion) {
+  let mut group = c.benchmark_group("elevate");
+  group.sample_size(10);
+  for size in SIZES.iter() {
+    let (width, height, spacing) = *size;
+    let points = perturb_points(SEED, generate_points(width, height, spacing), spacing, CHAOS);
+    group.bench_function(BenchmarkId::from_parameter(label(size)), |b| {
+      b.iter(|| elevate(SEED, &points, width, height))
+    });
+  }
+  group.finish();
+}
+
+fn bench_map(c: &mut Criterion) {
+  let mut group = c.benchmark_group("map_generate");
+  group.sample_size(10);
+  for size in SIZES.iter() {
+    let (width, height, spacing) = *size;
+    group.bench_function(BenchmarkId::new("default", label(size)), |b| {
+      b.iter(|| Map::generate("bench".to_string(), width, height, spacing, CHAOS))
+    });
+    group.bench_function(BenchmarkId::new("all_stages", label(size)), |b| {
+      b.iter(|| Map::generate_with("bench".to_string(), width, height, spacing, CHAOS, &all_stages()))
+    });
+  }
+  group.finish();
+}
+
+// `generateGrid` hands maps to JS through serde's JSON representation, so
+// serializing to JSON natively is a fair proxy for the cost of crossing over.
+fn bench_serialize(c: &mut Criterion) {
+  let mut group = c.benchmark_group("serialize");
+  for size in SIZES.iter() {
+    let (width, height, spacing) = *size;
+    let map = Map::generate_with("bench".to_string(), width, height, spacing, CHAOS, &all_stages());
+    group.bench_function(BenchmarkId::from_parameter(label(size)), |b| {
+      b.iter(|| serde_json::to_string(&map).unwrap())
+    });
+  }
+  group.finish();
+}
+
+criterion_group!(benches, bench_points, bench_elevate, bench_map, bench_serialize);
+criterion_main!(benches);
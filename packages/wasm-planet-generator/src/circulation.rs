@@ -0,0 +1,128 @@
+extern crate rand;
+
+use rand::rngs::StdRng;
+use std::f64::consts::PI;
+use crate::climate::latitude;
+use crate::distance::distance_field;
+use crate::map::Point;
+use crate::mesh::Mesh;
+use crate::slope::gradient;
+use crate::stage::{Draft, MapStage};
+
+/// Latitude span of each circulation cell (Hadley, Ferrel and polar), in
+/// degrees.
+const BAND: f64 = 30.0;
+
+/// Poleward share of the wind in each band, relative to its eastward share.
+/// Trade winds and polar easterlies blow towards the equator, westerlies
+/// towards the poles.
+const MERIDIONAL: f64 = 0.5;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct CirculationOptions {
+  /// How readily winds climbing a slope turn along it instead: they do
+  /// entirely once elevation rises by `1 / terrain_deflection` from one cell
+  /// to the next.
+  pub terrain_deflection: f64,
+  /// Distance from the coast, in map units, over which currents build up.
+  pub current_range: f64
+}
+
+impl Default for CirculationOptions {
+  fn default() -> Self {
+    CirculationOptions { terrain_deflection: 10.0, current_range: 60.0 }
+  }
+}
+
+// Vectors are in map units, `x` pointing east and `y` pointing south like
+// the map coordinates, the top of the map being north.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Circulation {
+  /// Prevailing wind of every cell, at most 1.0 long.
+  pub wind: Vec<Point>,
+  /// Surface current of every water cell, at most 1.0 long, and zero on land.
+  pub currents: Vec<Point>
+}
+
+pub struct CirculationStage(pub CirculationOptions);
+
+impl MapStage for CirculationStage {
+  fn name(&self) -> &str {
+    "circulation"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, _random: &mut StdRng) {
+    draft.layers.circulation = Some(simulate(draft.points, draft.elevation, draft.mesh, &self.0));
+  }
+}
+
+pub fn simulate(points: &[Point], elevation: &[f64], mesh: &Mesh, options: &CirculationOptions) -> Circulation {
+  Circulation { wind: winds(points, elevation, mesh, options), currents: currents(points, elevation, mesh, options) }
+}
+
+// Winds of the three circulation cells of each hemisphere, calm where cells
+// meet, turned along the slopes they would otherwise climb.
+fn winds(points: &[Point], elevation: &[f64], mesh: &Mesh, options: &CirculationOptions) -> Vec<Point> {
+  let land: Vec<f64> = elevation.iter().map(|value| value.max(0.0)).collect();
+
+  (0..points.len())
+    .map(|cell| {
+      let latitude = latitude(&points[cell], mesh);
+      let band = (latitude.abs() / BAND).floor().min(2.0);
+      let strength = (PI * (latitude.abs() - band * BAND) / BAND).sin();
+      let eastward = if band == 1.0 { 1.0 } else { -1.0 };
+      let northward = eastward * MERIDIONAL * latitude.signum();
+      let scale = strength / (1.0 + MERIDIONAL * MERIDIONAL).sqrt();
+      let wind = Point(eastward * scale, -northward * scale);
+
+      let slope = gradient(points, &land, mesh, cell);
+      let steepness = (slope.0 * slope.0 + slope.1 * slope.1).sqrt();
+      let climb = wind.0 * slope.0 + wind.1 * slope.1;
+      if climb <= 0.0 || steepness <= 0.0 {
+        return wind;
+      }
+      let deflection = (steepness * mesh.spacing * options.terrain_deflection).min(1.0) * climb / (steepness * steepness);
+      Point(wind.0 - slope.0 * deflection, wind.1 - slope.1 * deflection)
+    })
+    .collect()
+}
+
+// Wind-driven gyres, as the flow along the contours of a stream function
+// following the latitude bands: clockwise subtropical gyres in the northern
+// hemisphere, counterclockwise ones in the southern hemisphere, and westward
+// equatorial currents. The stream function fades out towards the coasts so
+// that currents run along them rather than into them.
+fn currents(points: &[Point], elevation: &[f64], mesh: &Mesh, options: &CirculationOptions) -> Vec<Point> {
+  let to_land = distance_field(points, mesh, (0..points.len()).filter(|cell| elevation[*cell] > 0.0));
+  let range = options.current_range.max(f64::EPSILON);
+  let stream: Vec<f64> = (0..points.len())
+    .map(|cell| {
+      let basin = 1.0 - (-to_land[cell] / range).exp();
+      (PI * latitude(&points[cell], mesh) / (2.0 * BAND)).sin() * basin
+    })
+    .collect();
+
+  let mut currents: Vec<Point> = (0..points.len())
+    .map(|cell| {
+      if elevation[cell] > 0.0 {
+        return Point(0.0, 0.0);
+      }
+      // With `y` pointing south, the flow is (dψ/dy, -dψ/dx).
+      let slope = gradient(points, &stream, mesh, cell);
+      Point(slope.1, -slope.0)
+    })
+    .collect();
+
+  let fastest = currents.iter().map(|current| (current.0 * current.0 + current.1 * current.1).sqrt()).fold(0.0, f64::max);
+  if fastest > 0.0 {
+    for current in currents.iter_mut() {
+      *current = Point(current.0 / fastest, current.1 / fastest);
+    }
+  }
+  currents
+}
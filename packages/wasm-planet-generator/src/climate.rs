@@ -0,0 +1,122 @@
+extern crate rand;
+
+use rand::rngs::StdRng;
+use crate::distance::distance_field;
+use crate::map::Point;
+use crate::mesh::Mesh;
+use crate::seasons::{self, Seasons};
+use crate::stage::{Draft, MapStage};
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ClimateOptions {
+  /// Mean temperature at sea level on the equator, which runs across the
+  /// middle of the map, in degrees Celsius.
+  pub equator_temperature: f64,
+  /// Mean temperature at sea level on the top and bottom map edges.
+  pub pole_temperature: f64,
+  /// Temperature drop between sea level and an elevation of 1.0.
+  pub lapse_rate: f64,
+  /// Distance, in map units, over which the moisture carried inland from
+  /// water and rivers fades out.
+  pub moisture_range: f64,
+  /// Models monthly temperature and precipitation.
+  pub seasons: bool,
+  /// Tilt of the planet axis, in degrees, which drives the seasons.
+  pub axial_tilt: f64,
+  /// Difference between the warmest month and the annual mean far inland at
+  /// the poles, for the axial tilt of the Earth.
+  pub seasonal_range: f64,
+  /// Precipitation of the wettest months in the wettest places, in
+  /// millimeters.
+  pub max_precipitation: f64
+}
+
+impl Default for ClimateOptions {
+  fn default() -> Self {
+    ClimateOptions {
+      equator_temperature: 27.0,
+      pole_temperature: -25.0,
+      lapse_rate: 40.0,
+      moisture_range: 150.0,
+      seasons: false,
+      axial_tilt: 23.44,
+      seasonal_range: 30.0,
+      max_precipitation: 150.0
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Climate {
+  /// Mean annual temperature, in degrees Celsius.
+  pub temperature: Vec<f64>,
+  /// From 0.0 (arid) to 1.0 (water cells).
+  pub moisture: Vec<f64>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub seasons: Option<Seasons>
+}
+
+pub struct ClimateStage(pub ClimateOptions);
+
+impl MapStage for ClimateStage {
+  fn name(&self) -> &str {
+    "climate"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, _random: &mut StdRng) {
+    let rivers = draft.layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+    let climate = simulate(draft.points, draft.elevation, rivers, draft.mesh, draft.temperature_offset, &self.0);
+    draft.layers.climate = Some(climate);
+  }
+}
+
+// Latitude of a point, from 90.0 degrees on the top edge of the map to -90.0
+// on the bottom one.
+pub fn latitude(point: &Point, mesh: &Mesh) -> f64 {
+  (0.5 - point.1 / mesh.height.max(f64::EPSILON)).clamp(-0.5, 0.5) * 180.0
+}
+
+// Temperature falls with latitude and elevation, and shifts with the global
+// `temperature_offset`. Moisture falls with the distance to the closest water
+// or river cell.
+pub fn simulate(
+  points: &[Point],
+  elevation: &[f64],
+  rivers: Option<&[bool]>,
+  mesh: &Mesh,
+  temperature_offset: f64,
+  options: &ClimateOptions
+) -> Climate {
+  let temperature: Vec<f64> = points
+    .iter()
+    .zip(elevation.iter())
+    .map(|(point, value)| {
+      let latitude = (point.1 / mesh.height.max(f64::EPSILON) * 2.0 - 1.0).abs().min(1.0);
+      let sea_level = options.equator_temperature + (options.pole_temperature - options.equator_temperature) * latitude
+        + temperature_offset;
+      sea_level - options.lapse_rate * value.max(0.0)
+    })
+    .collect();
+
+  let sources = (0..points.len())
+    .filter(|cell| elevation[*cell] <= 0.0 || rivers.is_some_and(|rivers| rivers[*cell]));
+  let range = options.moisture_range.max(f64::EPSILON);
+  let moisture: Vec<f64> = distance_field(points, mesh, sources)
+    .into_iter()
+    .map(|distance| (-distance / range).exp())
+    .collect();
+
+  let seasons = if options.seasons {
+    let latitudes: Vec<f64> = points.iter().map(|point| latitude(point, mesh)).collect();
+    Some(seasons::simulate(&latitudes, elevation, &temperature, &moisture, options))
+  } else {
+    None
+  };
+
+  Climate { temperature, moisture, seasons }
+}
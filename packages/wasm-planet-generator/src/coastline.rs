@@ -0,0 +1,149 @@
+use std::collections::{HashMap, VecDeque};
+use crate::map::Point;
+use crate::mesh::Mesh;
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct CoastlineOptions {
+  /// Number of Chaikin corner-cutting passes applied to every coastline.
+  pub smoothing: usize
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Coastline {
+  pub landmass: usize,
+  /// Whether the coastline borders a lake, i.e. water that does not connect
+  /// to the map edges, rather than the ocean.
+  pub lake: bool,
+  pub points: Vec<Point>
+}
+
+// Side of a land cell facing a water cell, oriented so that the land cell is
+// always on the same side. `water` is `None` past the map edges.
+struct Edge {
+  from: usize,
+  to: usize,
+  land: usize,
+  water: Option<usize>
+}
+
+// Chains the Voronoi sides between land and water cells into closed
+// polylines, one per landmass outline and one per hole within a landmass. Past
+// the map edges is considered water, so that landmasses cut by the map edges
+// stay closed.
+pub fn extract(
+  elevation: &[f64],
+  landmass_ids: &[Option<usize>],
+  mesh: &Mesh,
+  options: &CoastlineOptions
+) -> Vec<Coastline> {
+  if mesh.is_empty() {
+    return Vec::new();
+  }
+
+  let corners = &mesh.corners;
+  let lakes = find_lakes(elevation, mesh);
+  let edges = find_edges(elevation, mesh);
+
+  let mut outgoing: HashMap<usize, Vec<usize>> = HashMap::new();
+  for (index, edge) in edges.iter().enumerate() {
+    outgoing.entry(edge.from).or_default().push(index);
+  }
+
+  let mut used = vec![false; edges.len()];
+  let mut coastlines = Vec::new();
+
+  for start in 0..edges.len() {
+    if used[start] {
+      continue;
+    }
+
+    let mut polyline = Vec::new();
+    let mut lake = false;
+    let mut current = start;
+
+    loop {
+      used[current] = true;
+      let edge = &edges[current];
+      polyline.push(corners[edge.from].clone());
+      lake |= edge.water.is_some_and(|water| lakes[water]);
+
+      // Where two land cells only touch by a corner, keep following the cell
+      // we came from so that diagonal landmasses get their own outline.
+      let candidates = &outgoing[&edge.to];
+      let next = candidates
+        .iter()
+        .find(|candidate| !used[**candidate] && edges[**candidate].land == edge.land)
+        .or_else(|| candidates.iter().find(|candidate| !used[**candidate]));
+
+      match next {
+        Some(next) => current = *next,
+        None => break
+      }
+    }
+
+    let mut points = polyline;
+    for _ in 0..options.smoothing {
+      points = chaikin(&points);
+    }
+
+    coastlines.push(Coastline {
+      landmass: landmass_ids[edges[start].land].unwrap(),
+      lake,
+      points
+    });
+  }
+
+  coastlines
+}
+
+// Water cells that cannot reach the map edges.
+fn find_lakes(elevation: &[f64], mesh: &Mesh) -> Vec<bool> {
+  let mut lakes: Vec<bool> = elevation.iter().map(|value| *value <= 0.0).collect();
+  let mut queue = VecDeque::new();
+
+  for (cell, lake) in lakes.iter_mut().enumerate() {
+    if *lake && mesh.is_border(cell) {
+      *lake = false;
+      queue.push_back(cell);
+    }
+  }
+
+  while let Some(cell) = queue.pop_front() {
+    for neighbor in mesh.neighbors(cell) {
+      if lakes[neighbor] {
+        lakes[neighbor] = false;
+        queue.push_back(neighbor);
+      }
+    }
+  }
+
+  lakes
+}
+
+// Walks around every land cell, keeping the sides that face water.
+fn find_edges(elevation: &[f64], mesh: &Mesh) -> Vec<Edge> {
+  let mut edges = Vec::new();
+
+  for land in (0..mesh.len()).filter(|cell| elevation[*cell] > 0.0) {
+    for side in mesh.sides(land) {
+      if side.neighbor.is_none_or(|neighbor| elevation[neighbor] <= 0.0) {
+        edges.push(Edge { from: side.from, to: side.to, land, water: side.neighbor });
+      }
+    }
+  }
+
+  edges
+}
+
+fn chaikin(points: &[Point]) -> Vec<Point> {
+  let mut smoothed = Vec::with_capacity(points.len() * 2);
+
+  for (i, point) in points.iter().enumerate() {
+    let next = &points[(i + 1) % points.len()];
+    smoothed.push(Point(point.0 * 0.75 + next.0 * 0.25, point.1 * 0.75 + next.1 * 0.25));
+    smoothed.push(Point(point.0 * 0.25 + next.0 * 0.75, point.1 * 0.25 + next.1 * 0.75));
+  }
+
+  smoothed
+}
@@ -0,0 +1,111 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use crate::map::Point;
+use crate::mesh::{Mesh, distance};
+
+// Cell waiting in a `BinaryHeap`, ordered so that the lowest cost pops first.
+// Ties are broken on the cell index to keep the traversal deterministic.
+#[derive(PartialEq)]
+pub struct Candidate {
+  pub cost: f64,
+  pub cell: usize
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+  fn cmp(&self, other: &Self) -> Ordering {
+    other.cost
+      .partial_cmp(&self.cost)
+      .unwrap_or(Ordering::Equal)
+      .then_with(|| other.cell.cmp(&self.cell))
+  }
+}
+
+impl PartialOrd for Candidate {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct DistanceFields {
+  /// Distance to the nearest water cell for land cells, and negated distance
+  /// to the nearest land cell for water cells.
+  pub coast: Vec<f64>,
+  /// Distance to the nearest river cell, only when hydrology is enabled.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub river: Option<Vec<f64>>,
+  /// Distance to the closest map edge, over the cell graph to a border cell
+  /// then straight from that cell to the edge.
+  pub edge: Vec<f64>
+}
+
+// Shortest distance, in map units, from every cell to the closest source cell
+// along the cell adjacency graph. Cells that cannot reach a source are left at
+// infinity.
+pub fn distance_field(points: &[Point], mesh: &Mesh, sources: impl IntoIterator<Item = usize>) -> Vec<f64> {
+  offset_distance_field(points, mesh, sources.into_iter().map(|cell| (cell, 0.0)))
+}
+
+// Same as `distance_field`, with every source starting at its own distance.
+pub fn offset_distance_field(
+  points: &[Point],
+  mesh: &Mesh,
+  sources: impl IntoIterator<Item = (usize, f64)>
+) -> Vec<f64> {
+  let mut distances = vec![f64::INFINITY; points.len()];
+  let mut heap = BinaryHeap::new();
+
+  for (cell, cost) in sources {
+    if cost < distances[cell] {
+      distances[cell] = cost;
+      heap.push(Candidate { cost, cell });
+    }
+  }
+
+  while let Some(Candidate { cost, cell }) = heap.pop() {
+    if cost > distances[cell] {
+      continue;
+    }
+    for neighbor in mesh.neighbors(cell) {
+      let next = cost + distance(&points[cell], &points[neighbor]);
+      if next < distances[neighbor] {
+        distances[neighbor] = next;
+        heap.push(Candidate { cost: next, cell: neighbor });
+      }
+    }
+  }
+
+  distances
+}
+
+pub fn compute(
+  points: &[Point],
+  elevation: &[f64],
+  rivers: Option<&[bool]>,
+  mesh: &Mesh,
+  width: f64,
+  height: f64
+) -> DistanceFields {
+  let to_water = distance_field(points, mesh, (0..points.len()).filter(|cell| elevation[*cell] <= 0.0));
+  let to_land = distance_field(points, mesh, (0..points.len()).filter(|cell| elevation[*cell] > 0.0));
+
+  let coast = (0..points.len())
+    .map(|cell| if elevation[cell] > 0.0 { to_water[cell] } else { -to_land[cell] })
+    .collect();
+
+  let river = rivers.map(|rivers| {
+    distance_field(points, mesh, (0..points.len()).filter(|cell| rivers[*cell]))
+  });
+
+  // Border cells start from their straight distance to the map edge.
+  let to_edge = |point: &Point| point.0.min(point.1).min(width - point.0).min(height - point.1).max(0.0);
+  let edge = offset_distance_field(
+    points,
+    mesh,
+    (0..points.len()).filter(|cell| mesh.is_border(*cell)).map(|cell| (cell, to_edge(&points[cell])))
+  );
+
+  DistanceFields { coast, river, edge }
+}
@@ -4,98 +4,420 @@ extern crate noise;
 use crate::map::Point;
 use noise::*;
 
+use crate::progress::Cancelled;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
 // from https://github.com/Razaekel/noise-rs/blob/develop/examples/complexplanet.rs
 
+/// Frequency of the planet's continents. Higher frequency produces
+/// smaller, more numerous continents. This value is measured in radians.
+pub const CONTINENT_FREQUENCY: f64 = 0.4;
+
+/// Lacunarity of the planet's continents. Changing this value produces
+/// slightly different continents. For the best results, this value should
+/// be random, but close to 2.0.
+pub const CONTINENT_LACUNARITY: f64 = 2.208984375;
+
+/// Lacunarity of the planet's mountains. Changing the value produces
+/// slightly different mountains. For the best results, this value should
+/// be random, but close to 2.0.
+pub const MOUNTAIN_LACUNARITY: f64 = 2.142578125;
+
+/// Lacunarity of the planet's hills. Changing this value produces
+/// slightly different hills. For the best results, this value should be
+/// random, but close to 2.0.
+pub const HILLS_LACUNARITY: f64 = 2.162109375;
+
+/// Lacunarity of the planet's plains. Changing this value produces
+/// slightly different plains. For the best results, this value should be
+/// random, but close to 2.0.
+pub const PLAINS_LACUNARITY: f64 = 2.314453125;
+
+/// Lacunarity of the planet's badlands. Changing this value produces
+/// slightly different badlands. For the best results, this value should
+/// be random, but close to 2.0.
+pub const BADLANDS_LACUNARITY: f64 = 2.212890625;
+
+/// Specifies the "twistiness" of the mountains.
+pub const MOUNTAINS_TWIST: f64 = 1.0;
+
+/// Specifies the "twistiness" of the hills.
+pub const HILLS_TWIST: f64 = 1.0;
+
+/// Specifies the "twistiness" of the badlands.
+pub const BADLANDS_TWIST: f64 = 1.0;
+
+/// Specifies the planet's sea level. This value must be between -1.0
+/// (minimum planet elevation) and +1.0 (maximum planet elevation).
+pub const SEA_LEVEL: f64 = 0.0;
+
+/// Specifies the level on the planet in which continental shelves appear.
+/// This value must be between -1.0 (minimum planet elevation) and +1.0
+/// (maximum planet elevation), and must be less than `SEA_LEVEL`.
+pub const SHELF_LEVEL: f64 = -0.375;
+
+/// Determines the amount of mountainous terrain that appears on the
+/// planet. Values range from 0.0 (no mountains) to 1.0 (all terrain is
+/// covered in mountains). Mountains terrain will overlap hilly terrain.
+/// Because the badlands terrain may overlap parts of the mountainous
+/// terrain, setting `MOUNTAINS_AMOUNT` to 1.0 may not completely cover the
+/// terrain in mountains.
+pub const MOUNTAINS_AMOUNT: f64 = 0.5;
+
+/// Determines the amount of hilly terrain that appears on the planet.
+/// Values range from 0.0 (no hills) to 1.0 (all terrain is covered in
+/// hills). This value must be less than `MOUNTAINS_AMOUNT`. Because the
+/// mountains terrain will overlap parts of the hilly terrain, and the
+/// badlands terrain may overlap parts of the hilly terrain, setting
+/// `HILLS_AMOUNT` to 1.0 may not completely cover the terrain in hills.
+pub const HILLS_AMOUNT: f64 = (1.0 + MOUNTAINS_AMOUNT) / 2.0;
+
+/// Determines the amount of badlands terrain that covers the planet.
+/// Values range from 0.0 (no badlands) to 1.0 (all terrain is covered in
+/// badlands). Badlands terrain will overlap any other type of terrain.
+pub const BADLANDS_AMOUNT: f64 = 0.3125;
+
+/// Offset to apply to the terrain type definition. Low values (< 1.0)
+/// cause the rough areas to appear only at high elevations. High values
+/// (> 2.0) cause the rough areas to appear at any elevation. The
+/// percentage of rough areas on the planet are independent of this value.
+pub const TERRAIN_OFFSET: f64 = 1.0;
+
+/// Specifies the amount of "glaciation" on the mountains. This value
+/// should be close to 1.0 and greater than 1.0.
+pub const MOUNTAIN_GLACIATION: f64 = 1.375;
+
+/// Scaling to apply to the base continent elevations, in planetary
+/// elevation units.
+pub const CONTINENT_HEIGHT_SCALE: f64 = (1.0 - SEA_LEVEL) / 4.0;
+
+/// Maximum depth of the rivers, in planetary elevation units.
+pub const RIVER_DEPTH: f64 = 0.0234375;
+
+/// Values of the constants above, reported in the metadata of every map.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ElevationParameters {
+  pub continent_frequency: f64,
+  pub continent_lacunarity: f64,
+  pub mountain_lacunarity: f64,
+  pub hills_lacunarity: f64,
+  pub plains_lacunarity: f64,
+  pub badlands_lacunarity: f64,
+  pub mountains_twist: f64,
+  pub hills_twist: f64,
+  pub badlands_twist: f64,
+  pub sea_level: f64,
+  pub shelf_level: f64,
+  pub mountains_amount: f64,
+  pub hills_amount: f64,
+  pub badlands_amount: f64,
+  pub terrain_offset: f64,
+  pub mountain_glaciation: f64,
+  pub continent_height_scale: f64,
+  pub river_depth: f64
+}
+
+impl Default for ElevationParameters {
+  fn default() -> Self {
+    ElevationParameters {
+      continent_frequency: CONTINENT_FREQUENCY,
+      continent_lacunarity: CONTINENT_LACUNARITY,
+      mountain_lacunarity: MOUNTAIN_LACUNARITY,
+      hills_lacunarity: HILLS_LACUNARITY,
+      plains_lacunarity: PLAINS_LACUNARITY,
+      badlands_lacunarity: BADLANDS_LACUNARITY,
+      mountains_twist: MOUNTAINS_TWIST,
+      hills_twist: HILLS_TWIST,
+      badlands_twist: BADLANDS_TWIST,
+      sea_level: SEA_LEVEL,
+      shelf_level: SHELF_LEVEL,
+      mountains_amount: MOUNTAINS_AMOUNT,
+      hills_amount: HILLS_AMOUNT,
+      badlands_amount: BADLANDS_AMOUNT,
+      terrain_offset: TERRAIN_OFFSET,
+      mountain_glaciation: MOUNTAIN_GLACIATION,
+      continent_height_scale: CONTINENT_HEIGHT_SCALE,
+      river_depth: RIVER_DEPTH
+    }
+  }
+}
+
+/// Continent definition value under which the ocean floor lies, below the
+/// slope of the continental shelf (see the shelf-creator module).
+const OCEAN_FLOOR_LEVEL: f64 = -0.75;
+
+/// Output of the oceanic-trench module under which the ocean floor counts as
+/// a trench, 60% of the depth of the deepest trenches.
+const TRENCH_LEVEL: f64 = -0.15;
+
+// Kind of terrain the noise graph selected for a point, following the same
+// selector bounds as the graph itself.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Terrain {
+  Trench,
+  Ocean,
+  Shelf,
+  Plains,
+  Hill,
+  Mountain,
+  Badlands
+}
+
+/// Tectonic uplift from which land counts as mountains, and under which the
+/// sea floor counts as a trench, whatever the noise graph selected.
+const MOUNTAIN_UPLIFT: f64 = 0.25;
+const TRENCH_UPLIFT: f64 = -0.15;
+
+// Brings the terrain selected by the noise graph in line with the final
+// elevation, once tectonics and the target land ratio moved it: drowned land
+// becomes shelf, risen sea floor plains, and the uplift of plate boundaries,
+// scaled as it was applied, raises mountains and digs trenches.
+pub fn reclassify(terrain: &[Terrain], elevation: &[f64], uplift: Option<&[f64]>) -> Vec<Terrain> {
+  terrain
+    .iter()
+    .zip(elevation.iter())
+    .enumerate()
+    .map(|(cell, (kind, value))| {
+      let uplift = uplift.map_or(0.0, |uplift| uplift[cell]);
+      let land = *kind >= Terrain::Plains;
+      if *value <= SEA_LEVEL {
+        if uplift <= TRENCH_UPLIFT {
+          Terrain::Trench
+        } else if land {
+          Terrain::Shelf
+        } else {
+          *kind
+        }
+      } else if uplift >= MOUNTAIN_UPLIFT {
+        Terrain::Mountain
+      } else if land {
+        *kind
+      } else {
+        Terrain::Plains
+      }
+    })
+    .collect()
+}
+
+// Intermediate modules of the noise graph that can be output per point along
+// with the elevation, named after the modules of `elevate_chunk`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum NoiseLayer {
+  BaseContinentDef,
+  ContinentDef,
+  TerrainTypeDef,
+  MountainBaseDef,
+  MountainousHigh,
+  MountainousLow,
+  MountainousTerrain,
+  HillyTerrain,
+  PlainsTerrain,
+  BadlandsSand,
+  BadlandsCliffs,
+  BadlandsTerrain,
+  RiverPositions,
+  ScaledMountainousTerrain,
+  ScaledHillyTerrain,
+  ScaledPlainsTerrain,
+  ScaledBadlandsTerrain,
+  ContinentalShelf,
+  BaseContinentElev,
+  ContinentsWithPlains,
+  ContinentsWithHills,
+  ContinentsWithMountains,
+  ContinentsWithBadlands,
+  ContinentsWithRivers
+}
+
+impl NoiseLayer {
+  pub fn name(&self) -> &'static str {
+    match self {
+      NoiseLayer::BaseContinentDef => "baseContinentDef",
+      NoiseLayer::ContinentDef => "continentDef",
+      NoiseLayer::TerrainTypeDef => "terrainTypeDef",
+      NoiseLayer::MountainBaseDef => "mountainBaseDef",
+      NoiseLayer::MountainousHigh => "mountainousHigh",
+      NoiseLayer::MountainousLow => "mountainousLow",
+      NoiseLayer::MountainousTerrain => "mountainousTerrain",
+      NoiseLayer::HillyTerrain => "hillyTerrain",
+      NoiseLayer::PlainsTerrain => "plainsTerrain",
+      NoiseLayer::BadlandsSand => "badlandsSand",
+      NoiseLayer::BadlandsCliffs => "badlandsCliffs",
+      NoiseLayer::BadlandsTerrain => "badlandsTerrain",
+      NoiseLayer::RiverPositions => "riverPositions",
+      NoiseLayer::ScaledMountainousTerrain => "scaledMountainousTerrain",
+      NoiseLayer::ScaledHillyTerrain => "scaledHillyTerrain",
+      NoiseLayer::ScaledPlainsTerrain => "scaledPlainsTerrain",
+      NoiseLayer::ScaledBadlandsTerrain => "scaledBadlandsTerrain",
+      NoiseLayer::ContinentalShelf => "continentalShelf",
+      NoiseLayer::BaseContinentElev => "baseContinentElev",
+      NoiseLayer::ContinentsWithPlains => "continentsWithPlains",
+      NoiseLayer::ContinentsWithHills => "continentsWithHills",
+      NoiseLayer::ContinentsWithMountains => "continentsWithMountains",
+      NoiseLayer::ContinentsWithBadlands => "continentsWithBadlands",
+      NoiseLayer::ContinentsWithRivers => "continentsWithRivers"
+    }
+  }
+}
+
+/// Number of points handed to each thread when elevating in parallel. Every
+/// chunk builds its own noise graph, since cached modules can't be shared.
+#[cfg(feature = "parallel")]
+const CHUNK_SIZE: usize = 2048;
+
+/// Number of points elevated between two progress reports.
+#[cfg(not(feature = "parallel"))]
+const BATCH_SIZE: usize = 2048;
+#[cfg(feature = "parallel")]
+const BATCH_SIZE: usize = 8 * CHUNK_SIZE;
+
+// Elevation of the points, along with the values of every requested noise
+// layer, in the order they were requested, and their terrain when requested.
+// The elevation is left empty when it was given rather than evaluated.
+#[derive(Default)]
+pub struct Elevated {
+  pub elevation: Vec<f64>,
+  pub layers: Vec<Vec<f64>>,
+  pub terrain: Vec<Terrain>
+}
+
+impl Elevated {
+  fn append(&mut self, other: Elevated) {
+    self.elevation.extend(other.elevation);
+    self.terrain.extend(other.terrain);
+    self.layers.resize_with(other.layers.len(), Vec::new);
+    for (values, other) in self.layers.iter_mut().zip(other.layers) {
+      values.extend(other);
+    }
+  }
+}
+
+pub fn elevate(seed: u64, points: &[Point], width: f64, height: f64) -> Vec<f64> {
+  elevate_with_progress(seed, points, width, height, &mut |_| true)
+    .expect("elevation can't be cancelled without a progress callback")
+}
+
+pub fn elevate_with_progress(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  progress: &mut dyn FnMut(f64) -> bool
+) -> Result<Vec<f64>, Cancelled> {
+  elevate_with_layers(seed, points, width, height, &[], false, progress).map(|elevated| elevated.elevation)
+}
+
+// Elevates points batch by batch, reporting the share of elevated points after
+// each batch. Returning `false` from `progress` stops before the next batch.
+pub fn elevate_with_layers(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  layers: &[NoiseLayer],
+  terrain: bool,
+  progress: &mut dyn FnMut(f64) -> bool
+) -> Result<Elevated, Cancelled> {
+  evaluate(seed, points, width, height, None, layers, terrain, progress)
+}
+
+// Evaluates the noise layers and the terrain of points whose elevation is
+// already known, leaving the elevation itself out.
+#[allow(clippy::too_many_arguments)]
+pub fn sample_layers(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  elevation: &[f64],
+  layers: &[NoiseLayer],
+  terrain: bool,
+  progress: &mut dyn FnMut(f64) -> bool
+) -> Result<Elevated, Cancelled> {
+  evaluate(seed, points, width, height, Some(elevation), layers, terrain, progress)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn evaluate(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  elevation: Option<&[f64]>,
+  layers: &[NoiseLayer],
+  terrain: bool,
+  progress: &mut dyn FnMut(f64) -> bool
+) -> Result<Elevated, Cancelled> {
+  let mut elevated = Elevated::default();
+
+  for (index, batch) in points.chunks(BATCH_SIZE).enumerate() {
+    let start = index * BATCH_SIZE;
+    let known = elevation.map(|elevation| &elevation[start..start + batch.len()]);
+    elevated.append(elevate_batch(seed, batch, width, height, known, layers, terrain));
+    if !progress((start + batch.len()) as f64 / points.len() as f64) {
+      return Err(Cancelled);
+    }
+  }
+
+  Ok(elevated)
+}
+
+#[cfg(not(feature = "parallel"))]
+fn elevate_batch(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  elevation: Option<&[f64]>,
+  layers: &[NoiseLayer],
+  terrain: bool
+) -> Elevated {
+  elevate_chunk(seed, points, width, height, elevation, layers, terrain)
+}
+
+#[cfg(feature = "parallel")]
+fn elevate_batch(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  elevation: Option<&[f64]>,
+  layers: &[NoiseLayer],
+  terrain: bool
+) -> Elevated {
+  points
+    .par_chunks(CHUNK_SIZE)
+    .enumerate()
+    .map(|(index, chunk)| {
+      let start = index * CHUNK_SIZE;
+      let known = elevation.map(|elevation| &elevation[start..start + chunk.len()]);
+      elevate_chunk(seed, chunk, width, height, known, layers, terrain)
+    })
+    .collect::<Vec<Elevated>>()
+    .into_iter()
+    .fold(Elevated::default(), |mut elevated, chunk| {
+      elevated.append(chunk);
+      elevated
+    })
+}
+
 #[allow(non_snake_case)]
-pub fn elevate(seed: u64, points: &Vec<Point>, width: f64, height: f64) -> Vec<f64> {
+fn elevate_chunk(
+  seed: u64,
+  points: &[Point],
+  width: f64,
+  height: f64,
+  elevation: Option<&[f64]>,
+  layers: &[NoiseLayer],
+  terrain: bool
+) -> Elevated {
   // Planet seed. Change this to generate a different planet.
   let CURRENT_SEED: u32 = seed as u32;
 
-  /// Frequency of the planet's continents. Higher frequency produces
-  /// smaller, more numerous continents. This value is measured in radians.
-  const CONTINENT_FREQUENCY: f64 = 0.4;
-
-  /// Lacunarity of the planet's continents. Changing this value produces
-  /// slightly different continents. For the best results, this value should
-  /// be random, but close to 2.0.
-  const CONTINENT_LACUNARITY: f64 = 2.208984375;
-
-  /// Lacunarity of the planet's mountains. Changing the value produces
-  /// slightly different mountains. For the best results, this value should
-  /// be random, but close to 2.0.
-  const MOUNTAIN_LACUNARITY: f64 = 2.142578125;
-
-  /// Lacunarity of the planet's hills. Changing this value produces
-  /// slightly different hills. For the best results, this value should be
-  /// random, but close to 2.0.
-  const HILLS_LACUNARITY: f64 = 2.162109375;
-
-  /// Lacunarity of the planet's plains. Changing this value produces
-  /// slightly different plains. For the best results, this value should be
-  /// random, but close to 2.0.
-  const PLAINS_LACUNARITY: f64 = 2.314453125;
-
-  /// Lacunarity of the planet's badlands. Changing this value produces
-  /// slightly different badlands. For the best results, this value should
-  /// be random, but close to 2.0.
-  const BADLANDS_LACUNARITY: f64 = 2.212890625;
-
-  /// Specifies the "twistiness" of the mountains.
-  const MOUNTAINS_TWIST: f64 = 1.0;
-
-  /// Specifies the "twistiness" of the hills.
-  const HILLS_TWIST: f64 = 1.0;
-
-  /// Specifies the "twistiness" of the badlands.
-  const BADLANDS_TWIST: f64 = 1.0;
-
-  /// Specifies the planet's sea level. This value must be between -1.0
-  /// (minimum planet elevation) and +1.0 (maximum planet elevation).
-  const SEA_LEVEL: f64 = 0.0;
-
-  /// Specifies the level on the planet in which continental shelves appear.
-  /// This value must be between -1.0 (minimum planet elevation) and +1.0
-  /// (maximum planet elevation), and must be less than `SEA_LEVEL`.
-  const SHELF_LEVEL: f64 = -0.375;
-
-  /// Determines the amount of mountainous terrain that appears on the
-  /// planet. Values range from 0.0 (no mountains) to 1.0 (all terrain is
-  /// covered in mountains). Mountains terrain will overlap hilly terrain.
-  /// Because the badlands terrain may overlap parts of the mountainous
-  /// terrain, setting `MOUNTAINS_AMOUNT` to 1.0 may not completely cover the
-  /// terrain in mountains.
-  const MOUNTAINS_AMOUNT: f64 = 0.5;
-
-  /// Determines the amount of hilly terrain that appears on the planet.
-  /// Values range from 0.0 (no hills) to 1.0 (all terrain is covered in
-  /// hills). This value must be less than `MOUNTAINS_AMOUNT`. Because the
-  /// mountains terrain will overlap parts of the hilly terrain, and the
-  /// badlands terrain may overlap parts of the hilly terrain, setting
-  /// `HILLS_AMOUNT` to 1.0 may not completely cover the terrain in hills.
-  const HILLS_AMOUNT: f64 = (1.0 + MOUNTAINS_AMOUNT) / 2.0;
-
-  /// Determines the amount of badlands terrain that covers the planet.
-  /// Values range from 0.0 (no badlands) to 1.0 (all terrain is covered in
-  /// badlands). Badlands terrain will overlap any other type of terrain.
-  const BADLANDS_AMOUNT: f64 = 0.3125;
-
-  /// Offset to apply to the terrain type definition. Low values (< 1.0)
-  /// cause the rough areas to appear only at high elevations. High values
-  /// (> 2.0) cause the rough areas to appear at any elevation. The
-  /// percentage of rough areas on the planet are independent of this value.
-  const TERRAIN_OFFSET: f64 = 1.0;
-
-  /// Specifies the amount of "glaciation" on the mountains. This value
-  /// should be close to 1.0 and greater than 1.0.
-  const MOUNTAIN_GLACIATION: f64 = 1.375;
-
-  /// Scaling to apply to the base continent elevations, in planetary
-  /// elevation units.
-  const CONTINENT_HEIGHT_SCALE: f64 = (1.0 - SEA_LEVEL) / 4.0;
-
-  /// Maximum depth of the rivers, in planetary elevation units.
-  const RIVER_DEPTH: f64 = 0.0234375;
-
   // ////////////////////////////////////////////////////////////////////////
   // Function group: continent definition
   // ////////////////////////////////////////////////////////////////////////
@@ -1468,22 +1790,97 @@ pub fn elevate(seed: u64, points: &Vec<Point>, width: f64, height: f64) -> Vec<f
 
   // 1: [Unscaled-final-planet subgroup]: Caches the output value from the
   //    continent-with-rivers subgroup.
-  let unscaledFinalPlanet = Cache::new(continentsWithRivers);
+  let unscaledFinalPlanet = Cache::new(&continentsWithRivers);
 
   // Let's only generate the points that i'm interested in, instead of generating the whole NoseMap
   let x_bounds = Point(-2.0, 2.0);
   let y_bounds = Point(-2.0, 2.0);
   let x_extent = x_bounds.1 - x_bounds.0;
   let y_extent = y_bounds.1 - y_bounds.0;
-  let x_step = x_extent / width as f64;
-  let y_step = y_extent / height as f64;
+  let x_step = x_extent / width;
+  let y_step = y_extent / height;
 
-  points
+  let modules: Vec<&dyn NoiseFn<[f64; 3]>> = layers
     .iter()
-    .map(|point| {
-        let x = x_bounds.0 + x_step * point.0 as f64;
-        let y = y_bounds.0 + y_step * point.1 as f64;
-        unscaledFinalPlanet.get([x, y, 0.0])
+    .map(|layer| -> &dyn NoiseFn<[f64; 3]> {
+      match layer {
+        NoiseLayer::BaseContinentDef => &baseContinentDef,
+        NoiseLayer::ContinentDef => &continentDef,
+        NoiseLayer::TerrainTypeDef => &terrainTypeDef,
+        NoiseLayer::MountainBaseDef => &mountainBaseDef,
+        NoiseLayer::MountainousHigh => &mountainousHigh,
+        NoiseLayer::MountainousLow => &mountainousLow,
+        NoiseLayer::MountainousTerrain => &mountainousTerrain,
+        NoiseLayer::HillyTerrain => &hillyTerrain,
+        NoiseLayer::PlainsTerrain => &plainsTerrain,
+        NoiseLayer::BadlandsSand => &badlandsSand,
+        NoiseLayer::BadlandsCliffs => &badlandsCliffs,
+        NoiseLayer::BadlandsTerrain => &badlandsTerrain,
+        NoiseLayer::RiverPositions => &riverPositions,
+        NoiseLayer::ScaledMountainousTerrain => &scaledMountainousTerrain,
+        NoiseLayer::ScaledHillyTerrain => &scaledHillyTerrain,
+        NoiseLayer::ScaledPlainsTerrain => &scaledPlainsTerrain,
+        NoiseLayer::ScaledBadlandsTerrain => &scaledBadlandsTerrain,
+        NoiseLayer::ContinentalShelf => &continentalShelf,
+        NoiseLayer::BaseContinentElev => &baseContinentElev,
+        NoiseLayer::ContinentsWithPlains => &continentsWithPlains,
+        NoiseLayer::ContinentsWithHills => &continentsWithHills,
+        NoiseLayer::ContinentsWithMountains => &continentsWithMountains,
+        NoiseLayer::ContinentsWithBadlands => &continentsWithBadlands,
+        NoiseLayer::ContinentsWithRivers => &continentsWithRivers
+      }
     })
-    .collect::<Vec<f64>>()
+    .collect();
+  let mut elevated = Elevated {
+    elevation: Vec::with_capacity(if elevation.is_none() { points.len() } else { 0 }),
+    layers: vec![Vec::with_capacity(points.len()); layers.len()],
+    terrain: Vec::with_capacity(if terrain { points.len() } else { 0 })
+  };
+
+  for (index, point) in points.iter().enumerate() {
+    let x = x_bounds.0 + x_step * point.0;
+    let y = y_bounds.0 + y_step * point.1;
+    let value = match elevation {
+      Some(elevation) => elevation[index],
+      None => {
+        let value = unscaledFinalPlanet.get([x, y, 0.0]);
+        elevated.elevation.push(value);
+        value
+      }
+    };
+    for (values, module) in elevated.layers.iter_mut().zip(modules.iter()) {
+      values.push(module.get([x, y, 0.0]));
+    }
+
+    // Walks down the selectors of the graph: the continent definition picks
+    // the shelf over the continents, the terrain type definition mountains
+    // over hills over plains, and badlands poke out where they are higher.
+    if terrain {
+      let point = [x, y, 0.0];
+      let continent = continentDef.get(point);
+      let terrain_type = terrainTypeDef.get(point);
+      elevated.terrain.push(if continent < SHELF_LEVEL {
+        if continent >= OCEAN_FLOOR_LEVEL {
+          Terrain::Shelf
+        } else if continentalShelf_sb.get(point) < TRENCH_LEVEL {
+          Terrain::Trench
+        } else {
+          Terrain::Ocean
+        }
+      } else if value <= SEA_LEVEL {
+        Terrain::Shelf
+      } else if continentsWithBadlands_bm.get(point) > 1.0 - BADLANDS_AMOUNT
+        && continentsWithBadlands_ad.get(point) > continentsWithMountains.get(point) {
+        Terrain::Badlands
+      } else if terrain_type > 1.0 - MOUNTAINS_AMOUNT {
+        Terrain::Mountain
+      } else if terrain_type > 1.0 - HILLS_AMOUNT {
+        Terrain::Hill
+      } else {
+        Terrain::Plains
+      });
+    }
+  }
+
+  elevated
 }
@@ -0,0 +1,90 @@
+extern crate rand;
+
+use rand::rngs::StdRng;
+use std::collections::BinaryHeap;
+use crate::distance::Candidate;
+use crate::mesh::Mesh;
+use crate::stage::{Draft, MapStage};
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct HydrologyOptions {
+  /// Share of the map cells that must drain through a land cell for it to
+  /// carry a river.
+  pub river_threshold: f64
+}
+
+impl Default for HydrologyOptions {
+  fn default() -> Self {
+    HydrologyOptions { river_threshold: 0.002 }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Hydrology {
+  /// Cell each land cell drains into, `None` for water cells and for land
+  /// cells on the map border, which drain off the map.
+  pub downstream: Vec<Option<usize>>,
+  /// Number of land cells draining through each cell, itself included.
+  pub flux: Vec<f64>,
+  pub rivers: Vec<bool>
+}
+
+pub struct HydrologyStage(pub HydrologyOptions);
+
+impl MapStage for HydrologyStage {
+  fn name(&self) -> &str {
+    "hydrology"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, _random: &mut StdRng) {
+    draft.layers.hydrology = Some(drain(draft.elevation, draft.mesh, &self.0));
+  }
+}
+
+// Routes water from every land cell down to the sea. Drainage is computed with
+// a priority flood from water cells and map edges, which fills depressions so
+// that rivers never stop in a pit.
+pub fn drain(elevation: &[f64], mesh: &Mesh, options: &HydrologyOptions) -> Hydrology {
+  let count = elevation.len();
+  let mut downstream = vec![None; count];
+  let mut visited = vec![false; count];
+  let mut order = Vec::with_capacity(count);
+  let mut heap = BinaryHeap::new();
+
+  for cell in 0..count {
+    if elevation[cell] <= 0.0 || mesh.is_border(cell) {
+      visited[cell] = true;
+      heap.push(Candidate { cost: elevation[cell], cell });
+    }
+  }
+
+  while let Some(Candidate { cost, cell }) = heap.pop() {
+    order.push(cell);
+    for neighbor in mesh.neighbors(cell) {
+      if !visited[neighbor] {
+        visited[neighbor] = true;
+        downstream[neighbor] = Some(cell);
+        heap.push(Candidate { cost: elevation[neighbor].max(cost), cell: neighbor });
+      }
+    }
+  }
+
+  let mut flux: Vec<f64> = elevation.iter().map(|value| if *value > 0.0 { 1.0 } else { 0.0 }).collect();
+  for cell in order.iter().rev() {
+    if let Some(next) = downstream[*cell] {
+      flux[next] += flux[*cell];
+    }
+  }
+
+  let threshold = (options.river_threshold * count as f64).max(1.0);
+  let rivers = (0..count)
+    .map(|cell| elevation[cell] > 0.0 && flux[cell] >= threshold)
+    .collect();
+
+  Hydrology { downstream, flux, rivers }
+}
@@ -0,0 +1,138 @@
+extern crate rand;
+
+use rand::rngs::StdRng;
+use crate::climate::{self, ClimateOptions};
+use crate::mesh::Mesh;
+use crate::stage::{Draft, MapStage};
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct IceOptions {
+  /// Mean annual temperature, in degrees Celsius, under which land lies
+  /// under a permanent ice sheet.
+  pub sheet_temperature: f64,
+  /// Mean annual temperature under which the sea stays frozen.
+  pub sea_temperature: f64,
+  /// Mean annual temperature under which high land feeds a glacier.
+  pub glacier_temperature: f64,
+  /// Elevation from which cold land feeds a glacier.
+  pub glacier_elevation: f64,
+  /// Mean annual temperature from which glacier tongues melt away.
+  pub melt_temperature: f64
+}
+
+impl Default for IceOptions {
+  fn default() -> Self {
+    IceOptions {
+      sheet_temperature: -10.0,
+      sea_temperature: -5.0,
+      glacier_temperature: -2.0,
+      glacier_elevation: 0.25,
+      melt_temperature: 2.0
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IceKind {
+  Sheet,
+  Sea,
+  Glacier
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Ice {
+  /// Ice covering every cell, if any.
+  pub cells: Vec<Option<IceKind>>,
+  /// Cells of every glacier, from the one feeding it down to its tongue.
+  pub glaciers: Vec<Vec<usize>>
+}
+
+// Ice follows the temperatures of the climate, or those of the default
+// climate when the climate is disabled, the global temperature offset
+// included either way.
+pub struct IceStage(pub IceOptions);
+
+impl MapStage for IceStage {
+  fn name(&self) -> &str {
+    "ice"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, _random: &mut StdRng) {
+    let fallback;
+    let temperature = match &draft.layers.climate {
+      Some(climate) => &climate.temperature,
+      None => {
+        let rivers = draft.layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+        let (offset, options) = (draft.temperature_offset, ClimateOptions::default());
+        fallback = climate::simulate(draft.points, draft.elevation, rivers, draft.mesh, offset, &options);
+        &fallback.temperature
+      }
+    };
+    draft.layers.ice = Some(freeze(draft.elevation, temperature, draft.mesh, &self.0));
+  }
+}
+
+// Covers cold land with ice sheets and cold sea with sea ice, then lets
+// glaciers flow down the steepest slope from cold high ground until they
+// melt or reach the sea.
+pub fn freeze(elevation: &[f64], temperature: &[f64], mesh: &Mesh, options: &IceOptions) -> Ice {
+  let mut cells: Vec<Option<IceKind>> = (0..elevation.len())
+    .map(|cell| {
+      if elevation[cell] <= 0.0 {
+        if temperature[cell] <= options.sea_temperature { Some(IceKind::Sea) } else { None }
+      } else if temperature[cell] <= options.sheet_temperature {
+        Some(IceKind::Sheet)
+      } else {
+        None
+      }
+    })
+    .collect();
+
+  let mut sources: Vec<usize> = (0..elevation.len())
+    .filter(|cell| {
+      cells[*cell].is_none()
+        && elevation[*cell] >= options.glacier_elevation
+        && temperature[*cell] <= options.glacier_temperature
+    })
+    .collect();
+  // Glaciers start from the highest ground, those below join them.
+  sources.sort_by(|a, b| elevation[*b].partial_cmp(&elevation[*a]).unwrap().then(a.cmp(b)));
+
+  let mut glaciers = Vec::new();
+  for source in sources {
+    if cells[source].is_some() {
+      continue;
+    }
+    let mut glacier = vec![source];
+    cells[source] = Some(IceKind::Glacier);
+    let mut cell = source;
+
+    while let Some(next) = mesh.neighbors(cell)
+      .into_iter()
+      .filter(|neighbor| elevation[*neighbor] < elevation[cell])
+      .min_by(|a, b| elevation[*a].partial_cmp(&elevation[*b]).unwrap())
+    {
+      if elevation[next] <= 0.0 || temperature[next] >= options.melt_temperature {
+        break;
+      }
+      if cells[next].is_some() {
+        // Joins another glacier or an ice sheet.
+        glacier.push(next);
+        break;
+      }
+      cells[next] = Some(IceKind::Glacier);
+      glacier.push(next);
+      cell = next;
+    }
+
+    glaciers.push(glacier);
+  }
+
+  Ice { cells, glaciers }
+}
@@ -0,0 +1,91 @@
+use std::collections::VecDeque;
+use crate::map::Point;
+use crate::mesh::{Mesh, distance};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Bounds {
+  pub min: Point,
+  pub max: Point
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Landmass {
+  pub id: usize,
+  pub cells: usize,
+  /// Area of the cells, in squared map units.
+  pub area: f64,
+  /// Length of the cell sides facing water, in map units. Map edges don't
+  /// count.
+  pub coastline: f64,
+  pub bounds: Bounds,
+  /// Index of the highest cell of the landmass.
+  pub highest: usize,
+  pub highest_elevation: f64,
+  pub centroid: Point
+}
+
+// Labels connected land cells. Returns the landmass of every cell (`None` for
+// water) and the summary of each landmass, in discovery order.
+pub fn label(
+  points: &[Point],
+  elevation: &[f64],
+  mesh: &Mesh
+) -> (Vec<Option<usize>>, Vec<Landmass>) {
+  let mut landmass_ids = vec![None; points.len()];
+  let mut landmasses = Vec::new();
+  let mut queue = VecDeque::new();
+
+  for start in 0..points.len() {
+    if elevation[start] <= 0.0 || landmass_ids[start].is_some() {
+      continue;
+    }
+
+    let id = landmasses.len();
+    let mut landmass = Landmass {
+      id,
+      cells: 0,
+      area: 0.0,
+      coastline: 0.0,
+      bounds: Bounds { min: points[start].clone(), max: points[start].clone() },
+      highest: start,
+      highest_elevation: elevation[start],
+      centroid: Point(0.0, 0.0)
+    };
+
+    landmass_ids[start] = Some(id);
+    queue.push_back(start);
+
+    while let Some(cell) = queue.pop_front() {
+      let point = &points[cell];
+      landmass.cells += 1;
+      landmass.centroid.0 += point.0;
+      landmass.centroid.1 += point.1;
+      landmass.bounds.min = Point(landmass.bounds.min.0.min(point.0), landmass.bounds.min.1.min(point.1));
+      landmass.bounds.max = Point(landmass.bounds.max.0.max(point.0), landmass.bounds.max.1.max(point.1));
+      if elevation[cell] > landmass.highest_elevation {
+        landmass.highest = cell;
+        landmass.highest_elevation = elevation[cell];
+      }
+
+      landmass.area += mesh.area(cell);
+
+      for side in mesh.sides(cell) {
+        let Some(neighbor) = side.neighbor else { continue };
+        if elevation[neighbor] <= 0.0 {
+          landmass.coastline += distance(&mesh.corners[side.from], &mesh.corners[side.to]);
+        } else if landmass_ids[neighbor].is_none() {
+          landmass_ids[neighbor] = Some(id);
+          queue.push_back(neighbor);
+        }
+      }
+    }
+
+    landmass.centroid = Point(
+      landmass.centroid.0 / landmass.cells as f64,
+      landmass.centroid.1 / landmass.cells as f64
+    );
+    landmasses.push(landmass);
+  }
+
+  (landmass_ids, landmasses)
+}
@@ -3,16 +3,299 @@ extern crate serde_derive;
 extern crate console_error_panic_hook;
 
 use wasm_bindgen::prelude::*;
+use std::cell::RefCell;
 use std::panic;
 
-mod map;
-mod points;
-mod elevation;
+pub mod circulation;
+pub mod climate;
+pub mod coastline;
+pub mod map;
+pub mod mesh;
+pub mod meta;
+pub mod names;
+pub mod options;
+pub mod pathfinding;
+pub mod pipeline;
+pub mod points;
+pub mod progress;
+pub mod regions;
+pub mod resources;
+pub mod save;
+pub mod seasons;
+pub mod settlements;
+pub mod slope;
+pub mod roads;
+pub mod stage;
+pub mod distance;
+pub mod elevation;
+pub mod hydrology;
+pub mod ice;
+pub mod landmass;
+pub mod sea_level;
+pub mod tectonics;
 
 // API methods
+
+// `on_progress` is called with the stage name and its progress, from 0 to 1,
+// between chunks of work. Returning `false` from it cancels the generation,
+// which then throws. Exceptions thrown by `on_progress` stop the generation
+// too and are rethrown as they are.
+#[allow(deprecated)]
 #[wasm_bindgen(js_name = generateGrid)]
-pub fn _generate_grid(seed: String, width: f64, height: f64, spacing: f64, chaos: f64) -> JsValue {
+pub fn _generate_grid(
+  seed: String,
+  width: f64,
+  height: f64,
+  spacing: f64,
+  chaos: f64,
+  options: JsValue,
+  on_progress: Option<js_sys::Function>
+) -> Result<JsValue, JsValue> {
   panic::set_hook(Box::new(console_error_panic_hook::hook));
-  let map = map::Map::generate(seed, width, height, spacing, chaos);
-  JsValue::from_serde(&map).unwrap()
+  let options = parse_options(options)?;
+  let mut reporter = Reporter::new(&on_progress);
+  let map = map::Map::generate_with_progress(seed, width, height, spacing, chaos, &options, &mut reporter)
+    .map_err(|cancelled| reporter.error(cancelled))?;
+  Ok(JsValue::from_serde(&map).unwrap())
+}
+
+// Keeps the intermediate results of a generation around, so that generating
+// again with a few parameters changed only runs the stages they affect.
+#[derive(Default)]
+#[wasm_bindgen(js_name = Pipeline)]
+pub struct _Pipeline(pipeline::Pipeline);
+
+#[wasm_bindgen(js_class = Pipeline)]
+impl _Pipeline {
+  #[wasm_bindgen(constructor)]
+  pub fn new() -> Self {
+    _Pipeline::default()
+  }
+
+  // Same as `generateGrid`.
+  #[allow(deprecated, clippy::too_many_arguments)]
+  pub fn generate(
+    &mut self,
+    seed: String,
+    width: f64,
+    height: f64,
+    spacing: f64,
+    chaos: f64,
+    options: JsValue,
+    on_progress: Option<js_sys::Function>
+  ) -> Result<JsValue, JsValue> {
+    panic::set_hook(Box::new(console_error_panic_hook::hook));
+    let options = parse_options(options)?;
+    let parameters = pipeline::Parameters { seed, width, height, spacing, chaos };
+    let mut reporter = Reporter::new(&on_progress);
+    let map = self.0.generate(&parameters, &options, &mut reporter)
+      .map_err(|cancelled| reporter.error(cancelled))?;
+    Ok(JsValue::from_serde(&map).unwrap())
+  }
+
+  // Same as `findPath`, on the last generated map, without passing it back
+  // and forth.
+  #[wasm_bindgen(js_name = findPath)]
+  pub fn find_path(&self, from: usize, to: usize, costs: JsValue) -> Result<JsValue, JsValue> {
+    find_path(self.generated()?, from, to, costs)
+  }
+
+  // Same as `reachable`, on the last generated map.
+  pub fn reachable(&self, from: usize, budget: f64, costs: JsValue) -> Result<JsValue, JsValue> {
+    reachable(self.generated()?, from, budget, costs)
+  }
+
+  fn generated(&self) -> Result<&map::Map, JsValue> {
+    self.0.map().ok_or_else(|| JsValue::from_str("no map generated yet"))
+  }
+}
+
+// Cheapest path between two cells of a map returned by `generateGrid` or
+// `loadMap`, or `null`. `costs` is either a function of the two cells of a
+// step returning its cost, `null` when it can't be taken, or a cost table.
+#[allow(deprecated)]
+#[wasm_bindgen(js_name = findPath)]
+pub fn _find_path(map: JsValue, from: usize, to: usize, costs: JsValue) -> Result<JsValue, JsValue> {
+  let map: map::Map = map.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))?;
+  find_path(&map, from, to, costs)
+}
+
+// Cells of a map reachable from `from` for at most `budget`, as
+// `{ cell, cost }` by increasing cost. Takes the same costs as `findPath`.
+#[allow(deprecated)]
+#[wasm_bindgen(js_name = reachable)]
+pub fn _reachable(map: JsValue, from: usize, budget: f64, costs: JsValue) -> Result<JsValue, JsValue> {
+  let map: map::Map = map.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))?;
+  reachable(&map, from, budget, costs)
+}
+
+#[allow(deprecated)]
+fn find_path(map: &map::Map, from: usize, to: usize, costs: JsValue) -> Result<JsValue, JsValue> {
+  check_cell(map, from)?;
+  check_cell(map, to)?;
+  let costs = Costs::new(costs)?;
+  let path = pathfinding::find_path(map, from, to, &costs);
+  costs.check()?;
+  Ok(JsValue::from_serde(&path).unwrap())
+}
+
+#[allow(deprecated)]
+fn reachable(map: &map::Map, from: usize, budget: f64, costs: JsValue) -> Result<JsValue, JsValue> {
+  check_cell(map, from)?;
+  let costs = Costs::new(costs)?;
+  let reached = pathfinding::reachable(map, from, budget, &costs);
+  costs.check()?;
+  Ok(JsValue::from_serde(&reached).unwrap())
+}
+
+fn check_cell(map: &map::Map, cell: usize) -> Result<(), JsValue> {
+  if cell < map.points.len() {
+    Ok(())
+  } else {
+    Err(JsValue::from_str(&format!("no cell {}", cell)))
+  }
+}
+
+// Costs given to `findPath` and `reachable`. The first exception thrown by
+// the callback, or invalid cost it returns, ends the search and is thrown
+// once it is over.
+struct Costs {
+  callback: Option<js_sys::Function>,
+  table: pathfinding::CostTable,
+  error: RefCell<Option<JsValue>>
+}
+
+impl Costs {
+  #[allow(deprecated)]
+  fn new(costs: JsValue) -> Result<Self, JsValue> {
+    let (callback, table) = if costs.is_function() {
+      (Some(js_sys::Function::from(costs)), pathfinding::CostTable::default())
+    } else if costs.is_undefined() || costs.is_null() {
+      (None, pathfinding::CostTable::default())
+    } else {
+      (None, costs.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))?)
+    };
+    Ok(Costs { callback, table, error: RefCell::new(None) })
+  }
+
+  fn call(callback: &js_sys::Function, from: usize, to: usize) -> Result<Option<f64>, JsValue> {
+    let cost = callback.call2(&JsValue::NULL, &JsValue::from_f64(from as f64), &JsValue::from_f64(to as f64))?;
+    if cost.is_null() || cost.is_undefined() {
+      return Ok(None);
+    }
+    match cost.as_f64() {
+      Some(value) if value.is_finite() && value >= 0.0 => Ok(Some(value)),
+      _ => Err(JsValue::from_str(&format!(
+        "step costs must be finite non-negative numbers or null, found {:?} from {} to {}",
+        cost, from, to
+      )))
+    }
+  }
+
+  fn check(self) -> Result<(), JsValue> {
+    self.error.into_inner().map_or(Ok(()), Err)
+  }
+}
+
+impl pathfinding::Movement for Costs {
+  fn step(&self, map: &map::Map, from: usize, to: usize) -> Option<f64> {
+    let Some(callback) = &self.callback else { return self.table.step(map, from, to) };
+    if self.error.borrow().is_some() {
+      return None;
+    }
+    Costs::call(callback, from, to).unwrap_or_else(|error| {
+      self.error.replace(Some(error));
+      None
+    })
+  }
+
+  fn min_rate(&self) -> f64 {
+    if self.callback.is_some() { 0.0 } else { self.table.min_rate() }
+  }
+}
+
+#[allow(deprecated)]
+fn parse_options(options: JsValue) -> Result<options::Options, JsValue> {
+  if options.is_undefined() || options.is_null() {
+    Ok(options::Options::default())
+  } else {
+    options.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))
+  }
+}
+
+// Forwards progress to the JS callback, keeping the exception it throws, if
+// any, to rethrow it once the generation has stopped.
+struct Reporter<'a> {
+  callback: &'a Option<js_sys::Function>,
+  thrown: Option<JsValue>
+}
+
+impl<'a> Reporter<'a> {
+  fn new(callback: &'a Option<js_sys::Function>) -> Self {
+    Reporter { callback, thrown: None }
+  }
+
+  fn error(&mut self, cancelled: progress::Cancelled) -> JsValue {
+    self.thrown.take().unwrap_or_else(|| JsValue::from_str(&cancelled.to_string()))
+  }
+}
+
+impl<'a> progress::Progress for Reporter<'a> {
+  fn report(&mut self, stage: progress::Stage, done: f64) -> bool {
+    match self.callback {
+      Some(callback) => match callback.call2(&JsValue::NULL, &JsValue::from_str(stage.name()), &JsValue::from_f64(done)) {
+        Ok(result) => result.as_bool() != Some(false),
+        Err(exception) => {
+          self.thrown = Some(exception);
+          false
+        }
+      },
+      None => true
+    }
+  }
+}
+
+// Draws `count` distinct names from the given name options (culture, custom
+// words, lengths), the same ones for the same seed.
+#[allow(deprecated)]
+#[wasm_bindgen(js_name = generateNames)]
+pub fn _generate_names(seed: String, count: usize, options: JsValue) -> Result<JsValue, JsValue> {
+  let options: names::NameOptions = if options.is_undefined() || options.is_null() {
+    names::NameOptions::default()
+  } else {
+    options.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))?
+  };
+  let generator = names::NameGenerator::new(&options);
+  let mut random = rand::SeedableRng::seed_from_u64(map::seed_to_u64(&seed));
+  let mut used = std::collections::HashSet::new();
+  let names: Vec<String> = (0..count).map(|_| generator.generate(&mut random, &mut used)).collect();
+  Ok(JsValue::from_serde(&names).unwrap())
+}
+
+#[derive(Serialize)]
+struct LoadedMap {
+  header: save::Header,
+  map: map::Map
+}
+
+// Encodes a map returned by `generateGrid`, possibly edited since. The header
+// comes from the map metadata.
+#[allow(deprecated)]
+#[wasm_bindgen(js_name = saveMap)]
+pub fn _save_map(map: JsValue, save_options: JsValue) -> Result<Vec<u8>, JsValue> {
+  let save_options: save::SaveOptions = if save_options.is_undefined() || save_options.is_null() {
+    save::SaveOptions::default()
+  } else {
+    save_options.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))?
+  };
+  let map: map::Map = map.into_serde().map_err(|error| JsValue::from_str(&error.to_string()))?;
+  Ok(save::write(&map, &save_options))
+}
+
+// Decodes a save into `{ header, map }`, throwing on incompatible versions.
+#[allow(deprecated)]
+#[wasm_bindgen(js_name = loadMap)]
+pub fn _load_map(bytes: &[u8]) -> Result<JsValue, JsValue> {
+  let (header, map) = save::read(bytes).map_err(|error| JsValue::from_str(&error.to_string()))?;
+  Ok(JsValue::from_serde(&LoadedMap { header, map }).unwrap())
 }
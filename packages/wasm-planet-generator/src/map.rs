@@ -1,15 +1,79 @@
 extern crate serde_derive;
 
-use crate::points::{generate_points, perturb_points};
-use crate::elevation::elevate;
+use std::collections::BTreeMap;
+use crate::circulation::Circulation;
+use crate::climate::Climate;
+use crate::coastline::{self, Coastline};
+use crate::distance::{self, DistanceFields};
+use crate::elevation::Terrain;
+use crate::hydrology::Hydrology;
+use crate::ice::Ice;
+use crate::landmass::{self, Landmass};
+use crate::mesh::{Mesh, Subdivision};
+use crate::names::{self, Names};
+use crate::meta::Meta;
+use crate::options::Options;
+use crate::pipeline::{Parameters, Pipeline};
+use crate::regions::Regions;
+use crate::resources::Deposit;
+use crate::roads::Road;
+use crate::settlements::Settlement;
+use crate::slope::{self, Slope};
+use crate::progress::{Cancelled, Progress, Silent};
+use crate::sea_level;
+use crate::stage::Layers;
+use crate::tectonics::Tectonics;
 
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Point(pub f64, pub f64);
 
-#[derive(Serialize)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct Map {
+  pub meta: Meta,
   pub points: Vec<Point>,
-  pub elevation: Vec<f64>
+  /// Voronoi cells of the points, clipped to the map.
+  pub subdivision: Subdivision,
+  pub elevation: Vec<f64>,
+  pub land_ratio: f64,
+  pub landmass_ids: Vec<Option<usize>>,
+  pub landmasses: Vec<Landmass>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub coastlines: Option<Vec<Coastline>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub hydrology: Option<Hydrology>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub circulation: Option<Circulation>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub climate: Option<Climate>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub ice: Option<Ice>,
+  /// Deposits of every cell.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub resources: Option<Vec<Vec<Deposit>>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub regions: Option<Regions>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub settlements: Option<Vec<Settlement>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub roads: Option<Vec<Road>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub names: Option<Names>,
+  /// Layers added by custom stages, by name.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub layers: BTreeMap<String, Vec<f64>>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub slope: Option<Slope>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub distances: Option<DistanceFields>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub tectonics: Option<Tectonics>,
+  /// Terrain of every cell, as selected by the noise graph then brought in
+  /// line with tectonics and sea level rebasing.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub terrain: Option<Vec<Terrain>>,
+  /// Values of the requested noise layers, by name. Not saved.
+  #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+  pub noise_layers: BTreeMap<String, Vec<f64>>
 }
 
 impl Map {
@@ -20,19 +84,113 @@ impl Map {
     spacing: f64,
     chaos: f64
   ) -> Self {
-    let seed_value = seed_to_u64(seed);
-    let base_points = generate_points(width, height, spacing);
-    let points = perturb_points(seed_value, base_points, spacing, chaos);
-    let elevation = elevate(seed_value, &points, width, height);
+    Map::generate_with(seed, width, height, spacing, chaos, &Options::default())
+  }
+
+  pub fn generate_with(
+    seed: String,
+    width: f64,
+    height: f64,
+    spacing: f64,
+    chaos: f64,
+    options: &Options
+  ) -> Self {
+    Map::generate_with_progress(seed, width, height, spacing, chaos, options, &mut Silent)
+      .expect("generation can't be cancelled without a progress callback")
+  }
+
+  pub fn generate_with_progress(
+    seed: String,
+    width: f64,
+    height: f64,
+    spacing: f64,
+    chaos: f64,
+    options: &Options,
+    progress: &mut dyn Progress
+  ) -> Result<Self, Cancelled> {
+    let parameters = Parameters { seed, width, height, spacing, chaos };
+    Pipeline::new().generate(&parameters, options, progress)
+  }
+
+  // Triangulates the points again, for the passes run on a finished map.
+  pub fn mesh(&self) -> Mesh {
+    Mesh::new(&self.points, self.meta.width, self.meta.height, self.meta.spacing)
+  }
+
+  // Builds the map from its final elevation, deriving the landmasses,
+  // coastlines, slopes, distance fields and names. Shared by generation and
+  // loading.
+  pub(crate) fn assemble(
+    points: Vec<Point>,
+    elevation: Vec<f64>,
+    tectonics: Option<Tectonics>,
+    layers: Layers,
+    mesh: &Mesh,
+    meta: Meta,
+    progress: &mut dyn FnMut(f64) -> bool
+  ) -> Result<Self, Cancelled> {
+    let check = |progress: &mut dyn FnMut(f64) -> bool, done: f64| {
+      if progress(done) { Ok(()) } else { Err(Cancelled) }
+    };
+
+    let options = &meta.options;
+    let land_ratio = sea_level::land_ratio(&elevation);
+    let (landmass_ids, landmasses) = landmass::label(&points, &elevation, mesh);
+    check(progress, 1.0 / 3.0)?;
+
+    let coastlines = options.coastlines.as_ref().map(|coastline_options| {
+      coastline::extract(&elevation, &landmass_ids, mesh, coastline_options)
+    });
+    check(progress, 2.0 / 3.0)?;
+
+    let distances = if options.distances {
+      let rivers = layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+      Some(distance::compute(&points, &elevation, rivers, mesh, mesh.width, mesh.height))
+    } else {
+      None
+    };
+    let slope = options.slope.as_ref().map(|slope_options| slope::compute(&points, &elevation, mesh, slope_options));
+    let names = options.names.as_ref().map(|name_options| {
+      let settlements = layers.settlements.as_deref();
+      names::assign(meta.seed_value, &elevation, mesh, &landmasses, layers.hydrology.as_ref(), settlements, name_options)
+    });
+    check(progress, 1.0)?;
 
-    Map { points, elevation }
+    Ok(Map {
+      meta,
+      points,
+      subdivision: mesh.subdivision(),
+      elevation,
+      land_ratio,
+      landmass_ids,
+      landmasses,
+      coastlines,
+      hydrology: layers.hydrology,
+      circulation: layers.circulation,
+      climate: layers.climate,
+      ice: layers.ice,
+      resources: layers.resources,
+      regions: layers.regions,
+      settlements: layers.settlements,
+      roads: layers.roads,
+      names,
+      layers: layers.named,
+      slope,
+      distances,
+      tectonics,
+      terrain: None,
+      noise_layers: BTreeMap::new()
+    })
   }
 }
 
-fn seed_to_u64(seed: String, ) -> u64 {
+// Overflows on purpose: arithmetic wraps so that debug and release builds
+// derive the same value.
+pub(crate) fn seed_to_u64(seed: &str) -> u64 {
   let mut value: u64 = 0;
   for i in 0..seed.len() {
-    value += u64::pow(27, 256_u32 - i as u32 - 1_u32) * (1 + seed.as_bytes()[i] as u64)
+    let exponent = 256_u32.wrapping_sub(i as u32).wrapping_sub(1_u32);
+    value = value.wrapping_add(u64::wrapping_pow(27, exponent).wrapping_mul(1 + seed.as_bytes()[i] as u64))
   }
   value
 }
@@ -0,0 +1,208 @@
+extern crate spade;
+
+use spade::{DelaunayTriangulation, HasPosition, Point2, Triangulation};
+use crate::map::Point;
+
+// Points are generated as a perturbed lattice (see `generate_points`), column
+// by column, and their cells are the Voronoi cells of the points, clipped to
+// the map. Neighbours are read off the Delaunay triangulation, so a cell can
+// have more or fewer than four. The lattice is kept around for the passes
+// that walk it by columns and rows.
+//
+// The clipping comes from triangulating the points along with their mirrors
+// across the four map edges: the bisector between a point and its mirror is
+// the edge itself, so no cell reaches past the map.
+#[derive(Clone)]
+pub struct Mesh {
+  pub cols: usize,
+  pub rows: usize,
+  pub width: f64,
+  pub height: f64,
+  pub spacing: f64,
+  /// Vertices of the cells, shared by the cells around them.
+  pub corners: Vec<Point>,
+  sides: Vec<Vec<Side>>
+}
+
+/// Side of a cell, going from one of its corners to the next. Sides of a cell
+/// all turn the same way around it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Side {
+  pub from: usize,
+  pub to: usize,
+  /// Cell on the other side, `None` on the map edges.
+  pub neighbor: Option<usize>
+}
+
+/// Voronoi cells of the points, as laid out in generated maps.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Subdivision {
+  pub corners: Vec<Point>,
+  /// Corners of every cell, in order around it.
+  pub cells: Vec<Vec<usize>>
+}
+
+// Point given to the triangulation, `cell` being `None` for mirrors.
+struct Site {
+  position: Point2<f64>,
+  cell: Option<usize>
+}
+
+impl HasPosition for Site {
+  type Scalar = f64;
+
+  fn position(&self) -> Point2<f64> {
+    self.position
+  }
+}
+
+// Columns and rows of the lattice `generate_points` lays the points on.
+pub fn lattice(width: f64, height: f64, spacing: f64) -> (usize, usize) {
+  let count_width = (width / spacing) as i32;
+  let count_height = (height / spacing) as i32;
+  ((count_width - 1).max(0) as usize, (count_height - 1).max(0) as usize)
+}
+
+impl Mesh {
+  pub fn new(points: &[Point], width: f64, height: f64, spacing: f64) -> Self {
+    let (cols, rows) = lattice(width, height, spacing);
+
+    let mut sites: Vec<Site> = points
+      .iter()
+      .enumerate()
+      .map(|(cell, point)| Site { position: Point2::new(point.0, point.1), cell: Some(cell) })
+      .collect();
+    // Points on an edge would be their own mirror.
+    for point in points.iter().filter(|point| point.0 > 0.0 && point.0 < width && point.1 > 0.0 && point.1 < height) {
+      for (x, y) in [(-point.0, point.1), (2.0 * width - point.0, point.1), (point.0, -point.1), (point.0, 2.0 * height - point.1)] {
+        sites.push(Site { position: Point2::new(x, y), cell: None });
+      }
+    }
+    sites.retain(|site| spade::validate_vertex(site).is_ok());
+    // The stable variant keeps the first of duplicate sites, points before
+    // mirrors.
+    let triangulation = DelaunayTriangulation::<Site>::bulk_load_stable(sites).expect("sites are validated");
+
+    // Triangles whose circumcenters coincide, like the two halves of a square
+    // of unperturbed points, share a single corner, so that cells only
+    // touching by that corner aren't given a side of length zero.
+    let mut centers = vec![Point(0.0, 0.0); triangulation.num_all_faces()];
+    for face in triangulation.inner_faces() {
+      let center = face.circumcenter();
+      centers[face.fix().index()] = Point(center.x, center.y);
+    }
+    let tolerance = spacing.abs() * 1e-6;
+    let mut merged: Vec<usize> = (0..centers.len()).collect();
+    for face in triangulation.inner_faces() {
+      for edge in face.adjacent_edges().iter() {
+        let Some(other) = edge.rev().face().as_inner() else { continue };
+        let (face, other) = (face.fix().index(), other.fix().index());
+        if distance(&centers[face], &centers[other]) <= tolerance {
+          let (a, b) = (find(&mut merged, face), find(&mut merged, other));
+          merged[a.max(b)] = a.min(b);
+        }
+      }
+    }
+
+    let mut corner_ids = vec![None; centers.len()];
+    let mut corners = Vec::new();
+    let mut corner = |face: usize| {
+      let root = find(&mut merged, face);
+      *corner_ids[root].get_or_insert_with(|| {
+        let center = &centers[root];
+        corners.push(Point(center.0.clamp(0.0, width), center.1.clamp(0.0, height)));
+        corners.len() - 1
+      })
+    };
+    let mut sides = vec![Vec::new(); points.len()];
+
+    // Going around a point, the Voronoi side crossing each edge out of it runs
+    // from the triangle on the right of the edge to the one on its left.
+    for vertex in triangulation.vertices() {
+      let Some(cell) = vertex.data().cell else { continue };
+      for edge in vertex.out_edges() {
+        let (Some(right), Some(left)) = (edge.rev().face().as_inner(), edge.face().as_inner()) else { continue };
+        let (from, to) = (corner(right.fix().index()), corner(left.fix().index()));
+        if from != to {
+          sides[cell].push(Side { from, to, neighbor: edge.to().data().cell });
+        }
+      }
+    }
+
+    Mesh { cols, rows, width, height, spacing, corners, sides }
+  }
+
+  pub fn len(&self) -> usize {
+    self.sides.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  pub fn index(&self, col: usize, row: usize) -> usize {
+    col * self.rows + row
+  }
+
+  pub fn coords(&self, index: usize) -> (usize, usize) {
+    (index / self.rows, index % self.rows)
+  }
+
+  // Indices of the cells sharing a side with `index`, i.e. the points joined
+  // to it in the triangulation by an edge crossing its cell's outline.
+  pub fn neighbors(&self, index: usize) -> Vec<usize> {
+    self.sides[index].iter().filter_map(|side| side.neighbor).collect()
+  }
+
+  // Whether the cell reaches the map edges.
+  pub fn is_border(&self, index: usize) -> bool {
+    self.sides[index].iter().any(|side| side.neighbor.is_none())
+  }
+
+  pub fn sides(&self, cell: usize) -> &[Side] {
+    &self.sides[cell]
+  }
+
+  // Corners of the cell, in order around it.
+  pub fn polygon(&self, cell: usize) -> Vec<Point> {
+    self.sides[cell].iter().map(|side| self.corners[side.from].clone()).collect()
+  }
+
+  // Area of the cell, in squared map units.
+  pub fn area(&self, cell: usize) -> f64 {
+    let doubled: f64 = self.sides[cell]
+      .iter()
+      .map(|side| {
+        let (from, to) = (&self.corners[side.from], &self.corners[side.to]);
+        from.0 * to.1 - to.0 * from.1
+      })
+      .sum();
+    doubled.abs() / 2.0
+  }
+
+  pub fn subdivision(&self) -> Subdivision {
+    Subdivision {
+      corners: self.corners.clone(),
+      cells: self.sides.iter().map(|sides| sides.iter().map(|side| side.from).collect()).collect()
+    }
+  }
+}
+
+// Root of a set of merged triangles, flattening the path to it.
+fn find(merged: &mut [usize], face: usize) -> usize {
+  let mut root = face;
+  while merged[root] != root {
+    root = merged[root];
+  }
+  let mut face = face;
+  while merged[face] != root {
+    let next = merged[face];
+    merged[face] = root;
+    face = next;
+  }
+  root
+}
+
+pub fn distance(a: &Point, b: &Point) -> f64 {
+  ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
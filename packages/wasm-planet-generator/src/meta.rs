@@ -0,0 +1,105 @@
+// Everything needed to reproduce a map and to compare two generations: the
+// parameters it was generated with, the constants of the elevation noise and
+// the time spent in each stage.
+
+use serde::{Deserialize, Deserializer, Serializer};
+use crate::elevation::ElevationParameters;
+use crate::options::Options;
+use crate::progress::Stage;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Meta {
+  pub generator_version: String,
+  pub seed: String,
+  /// Numeric seed derived from `seed`. Serialized as a string, as it doesn't
+  /// fit in a JavaScript number.
+  #[serde(serialize_with = "serialize_u64", deserialize_with = "deserialize_u64")]
+  pub seed_value: u64,
+  pub width: f64,
+  pub height: f64,
+  pub spacing: f64,
+  pub chaos: f64,
+  pub options: Options,
+  pub elevation: ElevationParameters,
+  /// Time spent in each stage, in generation order. Empty for loaded maps.
+  pub timings: Vec<Timing>
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Timing {
+  pub stage: Stage,
+  pub milliseconds: f64
+}
+
+impl Meta {
+  // Metadata of a map generated by this version of the crate, before any
+  // stage has run.
+  pub fn new(
+    seed: String,
+    seed_value: u64,
+    width: f64,
+    height: f64,
+    spacing: f64,
+    chaos: f64,
+    options: Options
+  ) -> Self {
+    Meta {
+      generator_version: env!("CARGO_PKG_VERSION").to_string(),
+      seed,
+      seed_value,
+      width,
+      height,
+      spacing,
+      chaos,
+      options,
+      elevation: ElevationParameters::default(),
+      timings: Vec::new()
+    }
+  }
+}
+
+fn serialize_u64<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+  serializer.serialize_str(&value.to_string())
+}
+
+fn deserialize_u64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+  String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+}
+
+// Measures the time between laps, with `Date.now()` in the browser where
+// `std::time::Instant` isn't available.
+pub(crate) struct Stopwatch {
+  #[cfg(target_arch = "wasm32")]
+  last: f64,
+  #[cfg(not(target_arch = "wasm32"))]
+  last: std::time::Instant
+}
+
+impl Stopwatch {
+  #[cfg(target_arch = "wasm32")]
+  pub fn start() -> Self {
+    Stopwatch { last: js_sys::Date::now() }
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn start() -> Self {
+    Stopwatch { last: std::time::Instant::now() }
+  }
+
+  // Milliseconds elapsed since the previous lap, or since the start.
+  #[cfg(target_arch = "wasm32")]
+  pub fn lap(&mut self) -> f64 {
+    let now = js_sys::Date::now();
+    let elapsed = now - self.last;
+    self.last = now;
+    elapsed
+  }
+
+  #[cfg(not(target_arch = "wasm32"))]
+  pub fn lap(&mut self) -> f64 {
+    let now = std::time::Instant::now();
+    let elapsed = now.duration_since(self.last).as_secs_f64() * 1000.0;
+    self.last = now;
+    elapsed
+  }
+}
@@ -0,0 +1,290 @@
+extern crate rand;
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+use std::collections::{HashMap, HashSet, VecDeque};
+use crate::hydrology::Hydrology;
+use crate::landmass::Landmass;
+use crate::mesh::Mesh;
+use crate::settlements::Settlement;
+use crate::stage::stage_seed;
+
+/// Names drawn before settling for one shorter than asked, or numbering one
+/// already taken.
+const ATTEMPTS: usize = 100;
+
+const START: char = '^';
+const END: char = '$';
+
+const LATIN: &[&str] = &[
+  "roma", "aurelia", "valentia", "corduba", "tarraco", "hispalis", "lutetia", "massilia", "narbona", "burdigala",
+  "lugdunum", "augusta", "colonia", "aquileia", "ravenna", "verona", "brundisium", "capua", "neapolis", "syracusae",
+  "carthago", "utica", "emerita", "caesarea", "londinium", "eboracum", "mediolanum", "placentia", "florentia", "pisae",
+  "luca", "tergeste", "salona", "sirmium", "singidunum", "naissus", "serdica", "nicomedia", "ancyra", "antiochia"
+];
+
+const NORSE: &[&str] = &[
+  "bergen", "trondheim", "hedeby", "birka", "uppsala", "sigtuna", "roskilde", "odense", "ribe", "kaupang",
+  "tunsberg", "skiringssal", "jorvik", "dyflin", "reykjavik", "hafnarfjord", "thingvellir", "hlidarendi", "borg",
+  "helgafell", "vik", "stavanger", "agder", "halogaland", "vestfold", "gotland", "oland", "skane", "jutland", "fyn",
+  "ulfsby", "ravnsborg", "ormsvik", "grimsey", "hrafnsey", "thorsby", "eiriksfjord", "vinland", "markland", "hordaland"
+];
+
+const ELVISH: &[&str] = &[
+  "aelindor", "sylvaran", "ithilmar", "elenwen", "lothiel", "caladris", "mirathel", "nimrael", "faelar", "aerendis",
+  "thalanor", "valandril", "eldamar", "silvanis", "quelthas", "ilthuin", "naerith", "orlaith", "selenor", "taurien",
+  "amarath", "belthil", "celairen", "daervyn", "elunara", "filaren", "galadhor", "haldiren", "isilmae", "lindorae",
+  "maeloth", "nenuial", "oronel", "ravalin", "sarnith", "tirimel", "umbarel", "vanyael", "yavandir", "zaelith"
+];
+
+// Bundled word lists names can be modelled after.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Culture {
+  Latin,
+  Norse,
+  Elvish
+}
+
+impl Culture {
+  pub fn words(&self) -> &'static [&'static str] {
+    match self {
+      Culture::Latin => LATIN,
+      Culture::Norse => NORSE,
+      Culture::Elvish => ELVISH
+    }
+  }
+}
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct NameOptions {
+  pub culture: Culture,
+  /// Words to model names after instead of those of the culture, when not
+  /// empty.
+  pub words: Vec<String>,
+  /// Number of letters the next one is drawn from. Higher orders stick closer
+  /// to the words.
+  pub order: usize,
+  pub min_length: usize,
+  pub max_length: usize
+}
+
+impl Default for NameOptions {
+  fn default() -> Self {
+    NameOptions { culture: Culture::Latin, words: Vec::new(), order: 2, min_length: 4, max_length: 10 }
+  }
+}
+
+// Markov chain over the letters of a word list: every letter is drawn from
+// those following the `order` previous ones in the words.
+pub struct NameGenerator {
+  order: usize,
+  min_length: usize,
+  max_length: usize,
+  words: HashSet<String>,
+  followers: HashMap<Vec<char>, Vec<char>>
+}
+
+impl NameGenerator {
+  pub fn new(options: &NameOptions) -> Self {
+    let words: Vec<String> = if options.words.is_empty() {
+      options.culture.words().iter().map(|word| word.to_string()).collect()
+    } else {
+      options.words.iter().map(|word| word.trim().to_lowercase()).filter(|word| !word.is_empty()).collect()
+    };
+    let order = options.order.max(1);
+
+    let mut followers: HashMap<Vec<char>, Vec<char>> = HashMap::new();
+    for word in words.iter() {
+      let letters: Vec<char> = std::iter::repeat_n(START, order).chain(word.chars()).chain(Some(END)).collect();
+      for window in letters.windows(order + 1) {
+        followers.entry(window[..order].to_vec()).or_default().push(window[order]);
+      }
+    }
+
+    NameGenerator {
+      order,
+      min_length: options.min_length,
+      max_length: options.max_length.max(options.min_length).max(1),
+      words: words.into_iter().collect(),
+      followers
+    }
+  }
+
+  // Draws a name, preferably within the length bounds, never one of the
+  // words the chain was built from nor one in `used`, which it is added to.
+  // When the chain keeps drawing those, the last draw is numbered instead,
+  // as in "Roma 2".
+  pub fn generate(&self, random: &mut StdRng, used: &mut HashSet<String>) -> String {
+    let taken = |name: &str| self.words.contains(name) || used.contains(name);
+    let mut name = String::new();
+    for _ in 0..ATTEMPTS {
+      let (drawn, ended) = self.draw(random);
+      name = drawn;
+      if ended && name.chars().count() >= self.min_length && !taken(&name) {
+        break;
+      }
+    }
+
+    let base = name.clone();
+    let mut number = 1;
+    while taken(&name) {
+      number += 1;
+      name = if base.is_empty() { number.to_string() } else { format!("{} {}", base, number) };
+    }
+    used.insert(name.clone());
+
+    let mut letters = name.chars();
+    match letters.next() {
+      Some(first) => first.to_uppercase().chain(letters).collect(),
+      None => name
+    }
+  }
+
+  // Walks the chain until the end of a word or the maximum length, telling
+  // whether the word ended before being cut.
+  fn draw(&self, random: &mut StdRng) -> (String, bool) {
+    let mut context = vec![START; self.order];
+    let mut name = String::new();
+
+    while name.chars().count() < self.max_length {
+      let next = match self.followers.get(&context) {
+        Some(letters) => letters[random.gen_range(0, letters.len())],
+        None => END
+      };
+      if next == END {
+        return (name, true);
+      }
+      name.push(next);
+      context.remove(0);
+      context.push(next);
+    }
+
+    (name, false)
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct WaterBody {
+  pub id: usize,
+  pub cells: usize,
+  pub name: String
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct River {
+  /// Last river cell before the river reaches the sea.
+  pub mouth: usize,
+  /// Number of river cells draining through the mouth.
+  pub cells: usize,
+  pub name: String
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Names {
+  /// Names of the landmasses, by landmass id.
+  pub landmasses: Vec<String>,
+  /// Water body of every cell, `None` for land.
+  pub water_body_ids: Vec<Option<usize>>,
+  pub water_bodies: Vec<WaterBody>,
+  pub rivers: Vec<River>,
+  /// Names of the settlements, in the same order as the settlements.
+  pub settlements: Vec<String>
+}
+
+// Names the features of a map. Every kind of feature draws from its own
+// generator, seeded from the map seed, so that enabling settlements doesn't
+// rename the landmasses. No two features share a name, see
+// `NameGenerator::generate`.
+pub fn assign(
+  seed: u64,
+  elevation: &[f64],
+  mesh: &Mesh,
+  landmasses: &[Landmass],
+  hydrology: Option<&Hydrology>,
+  settlements: Option<&[Settlement]>,
+  options: &NameOptions
+) -> Names {
+  let generator = NameGenerator::new(options);
+  let mut used = HashSet::new();
+  let mut names = |kind: &str, count: usize| -> Vec<String> {
+    let mut random = StdRng::seed_from_u64(stage_seed(seed, &format!("names/{}", kind)));
+    (0..count).map(|_| generator.generate(&mut random, &mut used)).collect()
+  };
+
+  let landmass_names = names("landmasses", landmasses.len());
+
+  let (water_body_ids, sizes) = label_water(elevation, mesh);
+  let water_bodies = names("water_bodies", sizes.len())
+    .into_iter()
+    .zip(sizes)
+    .enumerate()
+    .map(|(id, (name, cells))| WaterBody { id, cells, name })
+    .collect();
+
+  let mouths = hydrology.map(find_mouths).unwrap_or_default();
+  let rivers = names("rivers", mouths.len())
+    .into_iter()
+    .zip(mouths)
+    .map(|(name, (mouth, cells))| River { mouth, cells, name })
+    .collect();
+
+  let settlement_names = names("settlements", settlements.map_or(0, |settlements| settlements.len()));
+
+  Names { landmasses: landmass_names, water_body_ids, water_bodies, rivers, settlements: settlement_names }
+}
+
+// Labels connected water cells, returning the water body of every cell and
+// the size of each body, in discovery order.
+fn label_water(elevation: &[f64], mesh: &Mesh) -> (Vec<Option<usize>>, Vec<usize>) {
+  let mut ids = vec![None; elevation.len()];
+  let mut sizes = Vec::new();
+  let mut queue = VecDeque::new();
+
+  for start in 0..elevation.len() {
+    if elevation[start] > 0.0 || ids[start].is_some() {
+      continue;
+    }
+    let id = sizes.len();
+    let mut size = 0;
+    ids[start] = Some(id);
+    queue.push_back(start);
+
+    while let Some(cell) = queue.pop_front() {
+      size += 1;
+      for neighbor in mesh.neighbors(cell) {
+        if elevation[neighbor] <= 0.0 && ids[neighbor].is_none() {
+          ids[neighbor] = Some(id);
+          queue.push_back(neighbor);
+        }
+      }
+    }
+    sizes.push(size);
+  }
+
+  (ids, sizes)
+}
+
+// River cells draining into something other than a river, along with the
+// number of river cells upstream of them, by increasing cell index. A cell
+// gathers more flux than any cell draining into it, so going down the flux
+// finds the mouth of every cell's downstream neighbour before its own.
+fn find_mouths(hydrology: &Hydrology) -> Vec<(usize, usize)> {
+  let rivers = &hydrology.rivers;
+  let mut order: Vec<usize> = (0..rivers.len()).filter(|cell| rivers[*cell]).collect();
+  order.sort_by(|a, b| hydrology.flux[*b].total_cmp(&hydrology.flux[*a]).then(a.cmp(b)));
+
+  let mut mouth_of = vec![0; rivers.len()];
+  let mut sizes: HashMap<usize, usize> = HashMap::new();
+  for cell in order {
+    mouth_of[cell] = match hydrology.downstream[cell].filter(|next| rivers[*next]) {
+      Some(next) => mouth_of[next],
+      None => cell
+    };
+    *sizes.entry(mouth_of[cell]).or_default() += 1;
+  }
+
+  let mut mouths: Vec<(usize, usize)> = sizes.into_iter().collect();
+  mouths.sort_unstable();
+  mouths
+}
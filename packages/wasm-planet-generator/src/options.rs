@@ -0,0 +1,59 @@
+use crate::circulation::CirculationOptions;
+use crate::climate::ClimateOptions;
+use crate::coastline::CoastlineOptions;
+use crate::elevation::NoiseLayer;
+use crate::hydrology::HydrologyOptions;
+use crate::ice::IceOptions;
+use crate::names::NameOptions;
+use crate::regions::RegionOptions;
+use crate::resources::ResourceOptions;
+use crate::roads::RoadOptions;
+use crate::settlements::SettlementOptions;
+use crate::slope::SlopeOptions;
+use crate::tectonics::TectonicsOptions;
+
+// Optional generation stages, deserialized from the options object passed to
+// `generateGrid`. Every stage is disabled when its options are left out.
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct Options {
+  /// Number of relaxation passes moving the perturbed points towards the
+  /// centroid of their cell.
+  pub relaxation: usize,
+  /// Intermediate modules of the elevation noise graph to output per point,
+  /// to see which part of the graph shaped a region.
+  pub noise_layers: Vec<NoiseLayer>,
+  /// Classifies the terrain of every cell, as selected by the noise graph and
+  /// reshaped by tectonics.
+  pub terrain: bool,
+  pub tectonics: Option<TectonicsOptions>,
+  /// Share of cells, from 0.0 to 1.0, to keep above sea level. Elevations are
+  /// rebased after generation so that sea level stays at 0.0.
+  pub target_land_ratio: Option<f64>,
+  pub coastlines: Option<CoastlineOptions>,
+  /// Added to every temperature, in degrees Celsius, to warm or cool the
+  /// whole planet: ice ages are a few degrees colder than the present. Drives
+  /// the climate and the ice, which follows the default climate when the
+  /// climate is disabled.
+  pub temperature_offset: f64,
+  pub hydrology: Option<HydrologyOptions>,
+  /// Prevailing winds and ocean currents.
+  pub circulation: Option<CirculationOptions>,
+  pub climate: Option<ClimateOptions>,
+  /// Ice sheets, sea ice and glaciers, following the temperatures of the
+  /// climate.
+  pub ice: Option<IceOptions>,
+  /// Ore, oil, fish and timber deposits, from the terrain, tectonics and
+  /// climate.
+  pub resources: Option<ResourceOptions>,
+  pub regions: Option<RegionOptions>,
+  pub settlements: Option<SettlementOptions>,
+  /// Links the settlements with roads, hence needs them.
+  pub roads: Option<RoadOptions>,
+  /// Names the landmasses, water bodies, rivers and settlements.
+  pub names: Option<NameOptions>,
+  /// Slope, aspect and surface normal of every cell.
+  pub slope: Option<SlopeOptions>,
+  /// Computes distance fields to the coast, rivers and map edges.
+  pub distances: bool
+}
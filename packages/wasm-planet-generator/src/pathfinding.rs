@@ -0,0 +1,220 @@
+use serde::{Deserialize, Deserializer};
+use std::collections::{BTreeMap, BinaryHeap};
+use crate::distance::Candidate;
+use crate::elevation::Terrain;
+use crate::map::Map;
+use crate::mesh::distance;
+use crate::seasons::Koppen;
+
+// Cost of moving between neighbouring cells of a map.
+pub trait Movement {
+  // Cost of a step from `from` to its neighbour `to`, `None` when it can't be
+  // taken. Negative and non-finite costs would break the searches, which
+  // don't take those steps either.
+  fn step(&self, map: &Map, from: usize, to: usize) -> Option<f64>;
+
+  // Lowest cost per map unit of any step, for A* to estimate the cost left to
+  // the goal. The default of 0.0 makes the search a plain Dijkstra.
+  fn min_rate(&self) -> f64 {
+    0.0
+  }
+}
+
+impl<F: Fn(&Map, usize, usize) -> Option<f64>> Movement for F {
+  fn step(&self, map: &Map, from: usize, to: usize) -> Option<f64> {
+    self(map, from, to)
+  }
+}
+
+// Movement costs as a table, for callers that can't provide a function.
+// Deserializing rejects negative and non-finite costs.
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct CostTable {
+  /// Cost per map unit over land.
+  #[serde(deserialize_with = "deserialize_cost")]
+  pub land: f64,
+  /// Cost per map unit over water, `None` when water can't be crossed.
+  #[serde(deserialize_with = "deserialize_optional_cost")]
+  pub water: Option<f64>,
+  /// Multipliers of the land cost by terrain, when the map has terrain.
+  /// Missing terrains keep a multiplier of 1.0.
+  #[serde(deserialize_with = "deserialize_multipliers")]
+  pub terrain: BTreeMap<Terrain, f64>,
+  /// Multipliers of the land cost by climate class, when the climate has
+  /// seasons. They stack with the terrain ones.
+  #[serde(deserialize_with = "deserialize_multipliers")]
+  pub koppen: BTreeMap<Koppen, f64>,
+  /// Extra cost per unit of elevation climbed.
+  #[serde(deserialize_with = "deserialize_cost")]
+  pub climb: f64,
+  /// Elevation above which land can't be entered.
+  pub max_elevation: Option<f64>,
+  /// Forbids land, for boats.
+  pub water_only: bool
+}
+
+impl Default for CostTable {
+  fn default() -> Self {
+    CostTable {
+      land: 1.0,
+      water: None,
+      terrain: BTreeMap::new(),
+      koppen: BTreeMap::new(),
+      climb: 0.0,
+      max_elevation: None,
+      water_only: false
+    }
+  }
+}
+
+impl Movement for CostTable {
+  fn step(&self, map: &Map, from: usize, to: usize) -> Option<f64> {
+    let length = distance(&map.points[from], &map.points[to]);
+    let elevation = map.elevation[to];
+    if elevation <= 0.0 {
+      return self.water.map(|rate| length * rate);
+    }
+    if self.water_only || self.max_elevation.is_some_and(|max| elevation > max) {
+      return None;
+    }
+
+    let terrain = map.terrain
+      .as_ref()
+      .and_then(|terrain| self.terrain.get(&terrain[to]))
+      .unwrap_or(&1.0);
+    let koppen = map.climate
+      .as_ref()
+      .and_then(|climate| climate.seasons.as_ref())
+      .and_then(|seasons| seasons.koppen[to])
+      .and_then(|koppen| self.koppen.get(&koppen))
+      .unwrap_or(&1.0);
+    let climb = (elevation - map.elevation[from].max(0.0)).max(0.0);
+    Some(length * self.land * terrain * koppen + climb * self.climb)
+  }
+
+  fn min_rate(&self) -> f64 {
+    let land = if self.water_only {
+      f64::INFINITY
+    } else {
+      lowest(self.terrain.values()) * lowest(self.koppen.values()) * self.land
+    };
+    land.min(self.water.unwrap_or(f64::INFINITY)).max(0.0)
+  }
+}
+
+// Lowest of a set of multipliers, missing ones standing for 1.0.
+fn lowest<'a>(multipliers: impl Iterator<Item = &'a f64>) -> f64 {
+  multipliers.fold(1.0, |min, multiplier| min.min(*multiplier))
+}
+
+fn check_cost<E: serde::de::Error>(cost: f64) -> Result<f64, E> {
+  if cost.is_finite() && cost >= 0.0 {
+    Ok(cost)
+  } else {
+    Err(E::custom(format!("costs must be finite and non-negative, found {}", cost)))
+  }
+}
+
+fn deserialize_cost<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+  check_cost(f64::deserialize(deserializer)?)
+}
+
+fn deserialize_optional_cost<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+  Option::<f64>::deserialize(deserializer)?.map(check_cost).transpose()
+}
+
+fn deserialize_multipliers<'de, D: Deserializer<'de>, K: Deserialize<'de> + Ord>(
+  deserializer: D
+) -> Result<BTreeMap<K, f64>, D::Error> {
+  let multipliers = BTreeMap::<K, f64>::deserialize(deserializer)?;
+  for multiplier in multipliers.values() {
+    check_cost::<D::Error>(*multiplier)?;
+  }
+  Ok(multipliers)
+}
+
+// Cost of a step, when it can be taken with a cost the searches can handle.
+fn valid_step(movement: &dyn Movement, map: &Map, from: usize, to: usize) -> Option<f64> {
+  movement.step(map, from, to).filter(|cost| cost.is_finite() && *cost >= 0.0)
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Path {
+  /// Cells from the start to the goal, both included.
+  pub cells: Vec<usize>,
+  pub cost: f64
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Reached {
+  pub cell: usize,
+  /// Cost of the cheapest path to the cell.
+  pub cost: f64
+}
+
+// Cheapest path between two cells with A*, `None` when the goal can't be
+// reached.
+pub fn find_path(map: &Map, from: usize, to: usize, movement: &dyn Movement) -> Option<Path> {
+  let mesh = map.mesh();
+  let rate = movement.min_rate();
+  let estimate = |cell: usize| distance(&map.points[cell], &map.points[to]) * rate;
+  let mut costs = vec![f64::INFINITY; map.points.len()];
+  let mut previous: Vec<Option<usize>> = vec![None; map.points.len()];
+  let mut heap = BinaryHeap::new();
+  costs[from] = 0.0;
+  heap.push(Candidate { cost: estimate(from), cell: from });
+
+  while let Some(Candidate { cost, cell }) = heap.pop() {
+    if cell == to {
+      let mut cells = vec![to];
+      while let Some(before) = previous[*cells.last().unwrap()] {
+        cells.push(before);
+      }
+      cells.reverse();
+      return Some(Path { cells, cost: costs[to] });
+    }
+    if cost > costs[cell] + estimate(cell) {
+      continue;
+    }
+    for neighbor in mesh.neighbors(cell) {
+      let Some(step) = valid_step(movement, map, cell, neighbor) else { continue };
+      let next = costs[cell] + step;
+      if next < costs[neighbor] {
+        costs[neighbor] = next;
+        previous[neighbor] = Some(cell);
+        heap.push(Candidate { cost: next + estimate(neighbor), cell: neighbor });
+      }
+    }
+  }
+
+  None
+}
+
+// Cells reachable from `from` for at most `budget`, the start included, by
+// increasing cost.
+pub fn reachable(map: &Map, from: usize, budget: f64, movement: &dyn Movement) -> Vec<Reached> {
+  let mesh = map.mesh();
+  let mut costs = vec![f64::INFINITY; map.points.len()];
+  let mut reached = Vec::new();
+  let mut heap = BinaryHeap::new();
+  costs[from] = 0.0;
+  heap.push(Candidate { cost: 0.0, cell: from });
+
+  while let Some(Candidate { cost, cell }) = heap.pop() {
+    if cost > costs[cell] {
+      continue;
+    }
+    reached.push(Reached { cell, cost });
+    for neighbor in mesh.neighbors(cell) {
+      let Some(step) = valid_step(movement, map, cell, neighbor) else { continue };
+      let next = cost + step;
+      if next <= budget && next < costs[neighbor] {
+        costs[neighbor] = next;
+        heap.push(Candidate { cost: next, cell: neighbor });
+      }
+    }
+  }
+
+  reached
+}
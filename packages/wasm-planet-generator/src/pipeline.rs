@@ -0,0 +1,363 @@
+extern crate rand;
+extern crate serde_json;
+
+use rand::SeedableRng;
+use crate::points::{generate_points, perturb_points, relax_points};
+use crate::elevation::{self, Elevated, elevate_with_progress, sample_layers};
+use crate::circulation::CirculationStage;
+use crate::climate::ClimateStage;
+use crate::hydrology::HydrologyStage;
+use crate::ice::IceStage;
+use crate::regions::RegionStage;
+use crate::resources::ResourceStage;
+use crate::roads::RoadStage;
+use crate::settlements::SettlementStage;
+use crate::map::{Map, Point, seed_to_u64};
+use crate::mesh::Mesh;
+use crate::meta::{Meta, Stopwatch, Timing};
+use crate::options::Options;
+use crate::progress::{Cancelled, Progress, Stage};
+use crate::sea_level;
+use crate::stage::{Draft, Layers, MapStage, stage_seed};
+use crate::tectonics::{self, Tectonics};
+
+/// Number of passes run after elevation, used to report their progress.
+const POST_PASSES: f64 = 6.0;
+
+// Steps of the pipeline, in the order they run. Several steps report their
+// progress under the same `Stage`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Step {
+  Points,
+  Perturb,
+  Relax,
+  Elevate,
+  /// Noise layers and terrain of the elevation graph, when requested.
+  NoiseLayers,
+  Tectonics,
+  SeaLevel,
+  Hydrology,
+  Circulation,
+  Climate,
+  Ice,
+  Resources,
+  Regions,
+  Settlements,
+  Roads,
+  /// Stages registered with `Pipeline::register`.
+  Custom,
+  /// Landmasses, coastlines, slopes and distance fields, derived from the
+  /// elevation.
+  Assemble
+}
+
+#[derive(Clone)]
+pub struct Parameters {
+  pub seed: String,
+  pub width: f64,
+  pub height: f64,
+  pub spacing: f64,
+  pub chaos: f64
+}
+
+// Result of a step along with a description of every input it was computed
+// from, its own and those of the steps upstream of it.
+struct Cached<T> {
+  key: String,
+  value: T
+}
+
+impl<T> Cached<T> {
+  // Computes the value again when the key changed, telling whether it did.
+  fn refresh(
+    slot: &mut Option<Cached<T>>,
+    key: &str,
+    compute: impl FnOnce() -> Result<T, Cancelled>
+  ) -> Result<bool, Cancelled> {
+    if slot.as_ref().is_some_and(|cached| cached.key == key) {
+      return Ok(false);
+    }
+    *slot = Some(Cached { key: key.to_string(), value: compute()? });
+    Ok(true)
+  }
+}
+
+// Generation split into steps whose results are cached. Running the pipeline
+// again only recomputes the steps whose inputs changed, and those downstream
+// of them: tweaking the coastlines doesn't move the points, nor elevate them.
+#[derive(Default)]
+pub struct Pipeline {
+  points: Option<Cached<Vec<Point>>>,
+  perturbed: Option<Cached<Vec<Point>>>,
+  relaxed: Option<Cached<(Vec<Point>, Mesh)>>,
+  elevation: Option<Cached<Vec<f64>>>,
+  noise_layers: Option<Cached<Elevated>>,
+  tectonics: Option<Cached<(Vec<f64>, Option<Tectonics>)>>,
+  sea_level: Option<Cached<Vec<f64>>>,
+  layers: Vec<Option<Cached<Layers>>>,
+  map: Option<Cached<Map>>,
+  stages: Vec<Box<dyn MapStage>>,
+  recomputed: Vec<Step>
+}
+
+impl Pipeline {
+  pub fn new() -> Self {
+    Pipeline::default()
+  }
+
+  pub fn generate(
+    &mut self,
+    parameters: &Parameters,
+    options: &Options,
+    progress: &mut dyn Progress
+  ) -> Result<Map, Cancelled> {
+    self.run(Step::Assemble, parameters, options, progress)?;
+    Ok(self.map.as_ref().unwrap().value.clone())
+  }
+
+  // Runs the steps up to `last` included, reusing the cached results of those
+  // whose inputs didn't change.
+  pub fn run(
+    &mut self,
+    last: Step,
+    parameters: &Parameters,
+    options: &Options,
+    progress: &mut dyn Progress
+  ) -> Result<(), Cancelled> {
+    let Parameters { seed, width, height, spacing, chaos } = parameters.clone();
+    let seed_value = seed_to_u64(&seed);
+    let mut meta = Meta::new(seed.clone(), seed_value, width, height, spacing, chaos, options.clone());
+    let mut stopwatch = Stopwatch::start();
+    self.recomputed.clear();
+
+    progress.check(Stage::Points, 0.0)?;
+    let key = format!("{:?} {:?} {:?}", width, height, spacing);
+    if Cached::refresh(&mut self.points, &key, || Ok(generate_points(width, height, spacing)))? {
+      self.recomputed.push(Step::Points);
+    }
+    meta.timings.push(Timing { stage: Stage::Points, milliseconds: stopwatch.lap() });
+    progress.check(Stage::Points, 1.0)?;
+    if last == Step::Points {
+      return Ok(());
+    }
+
+    let key = format!("{} {:?} {:?}", key, seed, chaos);
+    let points = &self.points.as_ref().unwrap().value;
+    if Cached::refresh(&mut self.perturbed, &key, || Ok(perturb_points(seed_value, points.clone(), spacing, chaos)))? {
+      self.recomputed.push(Step::Perturb);
+    }
+    if last == Step::Perturb {
+      return progress.check(Stage::Perturbation, 1.0);
+    }
+
+    let key = format!("{} {}", key, options.relaxation);
+    let perturbed = &self.perturbed.as_ref().unwrap().value;
+    let refreshed = Cached::refresh(&mut self.relaxed, &key, || {
+      let points = relax_points(perturbed.clone(), width, height, spacing, options.relaxation);
+      let mesh = Mesh::new(&points, width, height, spacing);
+      Ok((points, mesh))
+    })?;
+    if refreshed {
+      self.recomputed.push(Step::Relax);
+    }
+    meta.timings.push(Timing { stage: Stage::Perturbation, milliseconds: stopwatch.lap() });
+    progress.check(Stage::Perturbation, 1.0)?;
+    if last == Step::Relax {
+      return Ok(());
+    }
+
+    let (points, mesh) = &self.relaxed.as_ref().unwrap().value;
+    let refreshed = Cached::refresh(&mut self.elevation, &key, || {
+      elevate_with_progress(seed_value, points, width, height, &mut |done| progress.report(Stage::Elevation, done))
+    })?;
+    if refreshed {
+      self.recomputed.push(Step::Elevate);
+    }
+    if last == Step::Elevate {
+      return Ok(());
+    }
+
+    // Noise layers and terrain don't change the elevation, so they are left
+    // out of the key handed down to the next steps. The step is skipped when
+    // neither is requested.
+    let elevated = &self.elevation.as_ref().unwrap().value;
+    let sampled = !options.noise_layers.is_empty() || options.terrain;
+    if sampled {
+      let layers_key = format!("{} {:?} {}", key, options.noise_layers, options.terrain);
+      let refreshed = Cached::refresh(&mut self.noise_layers, &layers_key, || {
+        sample_layers(seed_value, points, width, height, elevated, &options.noise_layers, options.terrain, &mut |_| {
+          progress.report(Stage::Elevation, 1.0)
+        })
+      })?;
+      if refreshed {
+        self.recomputed.push(Step::NoiseLayers);
+      }
+    }
+    let noise_layers = self.noise_layers.as_ref().filter(|_| sampled).map(|cached| &cached.value);
+    meta.timings.push(Timing { stage: Stage::Elevation, milliseconds: stopwatch.lap() });
+    if last == Step::NoiseLayers {
+      return Ok(());
+    }
+
+    let key = format!("{} {}", key, serde_json::to_string(&options.tectonics).unwrap());
+    let refreshed = Cached::refresh(&mut self.tectonics, &key, || {
+      let mut elevation = elevated.clone();
+      let tectonics = options.tectonics.as_ref().map(|tectonics_options| {
+        let tectonics = tectonics::simulate(seed_value, points, mesh, tectonics_options);
+        tectonics::deform(&mut elevation, &tectonics, tectonics_options);
+        tectonics
+      });
+      Ok((elevation, tectonics))
+    })?;
+    if refreshed {
+      self.recomputed.push(Step::Tectonics);
+    }
+    progress.check(Stage::PostProcessing, 1.0 / POST_PASSES)?;
+    if last == Step::Tectonics {
+      return Ok(());
+    }
+
+    let key = format!("{} {:?}", key, options.target_land_ratio);
+    let (elevation, _) = &self.tectonics.as_ref().unwrap().value;
+    let refreshed = Cached::refresh(&mut self.sea_level, &key, || {
+      let mut elevation = elevation.clone();
+      if let Some(target_land_ratio) = options.target_land_ratio {
+        sea_level::rebase(&mut elevation, target_land_ratio);
+      }
+      Ok(elevation)
+    })?;
+    if refreshed {
+      self.recomputed.push(Step::SeaLevel);
+    }
+    progress.check(Stage::PostProcessing, 2.0 / POST_PASSES)?;
+    if last == Step::SeaLevel {
+      return Ok(());
+    }
+
+    let hydrology_stage = options.hydrology.clone().map(HydrologyStage);
+    let circulation_stage = options.circulation.clone().map(CirculationStage);
+    let climate_stage = options.climate.clone().map(ClimateStage);
+    let ice_stage = options.ice.clone().map(IceStage);
+    let resource_stage = options.resources.clone().map(ResourceStage);
+    let region_stage = options.regions.clone().map(RegionStage);
+    let settlement_stage = options.settlements.clone().map(SettlementStage);
+    let road_stage = options.roads.clone().map(RoadStage);
+    let mut stages: Vec<(Step, &dyn MapStage)> = Vec::new();
+    if let Some(stage) = &hydrology_stage {
+      stages.push((Step::Hydrology, stage));
+    }
+    if let Some(stage) = &circulation_stage {
+      stages.push((Step::Circulation, stage));
+    }
+    if let Some(stage) = &climate_stage {
+      stages.push((Step::Climate, stage));
+    }
+    if let Some(stage) = &ice_stage {
+      stages.push((Step::Ice, stage));
+    }
+    if let Some(stage) = &resource_stage {
+      stages.push((Step::Resources, stage));
+    }
+    if let Some(stage) = &region_stage {
+      stages.push((Step::Regions, stage));
+    }
+    if let Some(stage) = &settlement_stage {
+      stages.push((Step::Settlements, stage));
+    }
+    if let Some(stage) = &road_stage {
+      stages.push((Step::Roads, stage));
+    }
+    stages.extend(self.stages.iter().map(|stage| (Step::Custom, stage.as_ref())));
+
+    // Stages see the terrain when it is classified and the temperature offset,
+    // so both are part of their key. The terrain follows the final elevation.
+    let elevation = &self.sea_level.as_ref().unwrap().value;
+    let (_, tectonics) = &self.tectonics.as_ref().unwrap().value;
+    let tectonics = tectonics.as_ref();
+    let classified = if let Some(sampled) = noise_layers.filter(|_| options.terrain) {
+      let uplift = tectonics.zip(options.tectonics.as_ref()).map(|(tectonics, tectonics_options)| {
+        tectonics.uplift.iter().map(|value| value * tectonics_options.uplift).collect::<Vec<f64>>()
+      });
+      Some(elevation::reclassify(&sampled.terrain, elevation, uplift.as_deref()))
+    } else {
+      None
+    };
+    let terrain = classified.as_deref();
+    let temperature_offset = options.temperature_offset;
+    let mut key = format!("{} {} {:?}", key, options.terrain, temperature_offset);
+    self.layers.resize_with(stages.len(), || None);
+    for (index, (step, stage)) in stages.iter().enumerate() {
+      if *step > last {
+        break;
+      }
+
+      key = format!("{} {} {}", key, stage.name(), stage.key());
+      let (upstream, slots) = self.layers.split_at_mut(index);
+      let refreshed = Cached::refresh(&mut slots[0], &key, || {
+        let layers = upstream.last().map_or_else(Layers::default, |cached| cached.as_ref().unwrap().value.clone());
+        let mut draft = Draft { points, mesh, elevation, terrain, tectonics, temperature_offset, layers };
+        let mut random = SeedableRng::seed_from_u64(stage_seed(seed_value, stage.name()));
+        stage.run(&mut draft, &mut random);
+        Ok(draft.layers)
+      })?;
+      if refreshed {
+        self.recomputed.push(*step);
+      }
+      progress.check(Stage::PostProcessing, (2.0 + (index + 1) as f64 / stages.len() as f64) / POST_PASSES)?;
+    }
+    progress.check(Stage::PostProcessing, 3.0 / POST_PASSES)?;
+    if last < Step::Assemble {
+      return Ok(());
+    }
+
+    let key = format!("{} {}", key, serde_json::to_string(options).unwrap());
+    let mut timings = meta.timings.clone();
+    let (_, tectonics) = &self.tectonics.as_ref().unwrap().value;
+    let layers = self.layers.last().map(|cached| &cached.as_ref().unwrap().value);
+    let refreshed = Cached::refresh(&mut self.map, &key, || {
+      let layers = layers.cloned().unwrap_or_default();
+      let mut map = Map::assemble(points.clone(), elevation.clone(), tectonics.clone(), layers, mesh, meta, &mut |done| {
+        progress.report(Stage::PostProcessing, (3.0 + done * 3.0) / POST_PASSES)
+      })?;
+      map.noise_layers = options.noise_layers
+        .iter()
+        .zip(noise_layers.iter().flat_map(|sampled| sampled.layers.iter()))
+        .map(|(layer, values)| (layer.name().to_string(), values.clone()))
+        .collect();
+      map.terrain = classified.clone();
+      Ok(map)
+    })?;
+    if refreshed {
+      self.recomputed.push(Step::Assemble);
+    }
+    // Timings are those of this run, even when the map itself was reused.
+    timings.push(Timing { stage: Stage::PostProcessing, milliseconds: stopwatch.lap() });
+    self.map.as_mut().unwrap().value.meta.timings = timings;
+    Ok(())
+  }
+
+  // Adds a stage run after the built-in ones, in registration order.
+  pub fn register(&mut self, stage: Box<dyn MapStage>) {
+    self.stages.push(stage);
+  }
+
+  // Steps computed again by the last run, the others having been reused.
+  pub fn recomputed(&self) -> &[Step] {
+    &self.recomputed
+  }
+
+  // Map of the last run, when it went as far as assembling it.
+  pub fn map(&self) -> Option<&Map> {
+    self.map.as_ref().map(|cached| &cached.value)
+  }
+
+  // Final points, once relaxed.
+  pub fn points(&self) -> Option<&[Point]> {
+    self.relaxed.as_ref().map(|cached| cached.value.0.as_slice())
+  }
+
+  // Final elevation, once deformed and rebased.
+  pub fn elevation(&self) -> Option<&[f64]> {
+    self.sea_level.as_ref().map(|cached| cached.value.as_slice())
+  }
+}
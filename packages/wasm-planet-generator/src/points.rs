@@ -1,8 +1,9 @@
 
-extern crate rand;
-
-use rand::{Rng, SeedableRng, rngs::StdRng};
 use crate::map::Point;
+use crate::mesh::Mesh;
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 pub fn generate_points(width: f64, height: f64, spacing: f64) -> Vec<Point> {
   let mut points = Vec::new();
@@ -21,17 +22,65 @@ pub fn generate_points(width: f64, height: f64, spacing: f64) -> Vec<Point> {
 }
 
 pub fn perturb_points(seed: u64, points: Vec<Point>, spacing: f64, chaos: f64) -> Vec<Point> {
-  let mut random: StdRng = SeedableRng::seed_from_u64(seed);
+  #[cfg(not(feature = "parallel"))]
+  let iter = points.iter();
+  #[cfg(feature = "parallel")]
+  let iter = points.par_iter();
 
-  points
-    .iter()
-    .map(|point| Point (
-      perturb_point_coord(point.0, spacing, chaos, random.gen::<f64>()),
-      perturb_point_coord(point.1, spacing, chaos, random.gen::<f64>())
+  iter
+    .enumerate()
+    .map(|(index, point)| Point (
+      perturb_point_coord(point.0, spacing, chaos, indexed_random(seed, 2 * index as u64)),
+      perturb_point_coord(point.1, spacing, chaos, indexed_random(seed, 2 * index as u64 + 1))
     ))
     .collect::<Vec<Point>>()
 }
 
+// Moves every point to the centroid of its Voronoi cell, `iterations` times,
+// evening out the cell sizes (Lloyd relaxation).
+pub fn relax_points(points: Vec<Point>, width: f64, height: f64, spacing: f64, iterations: usize) -> Vec<Point> {
+  let mut points = points;
+
+  for _ in 0..iterations {
+    let mesh = Mesh::new(&points, width, height, spacing);
+    points = points
+      .iter()
+      .enumerate()
+      .map(|(cell, point)| centroid(&mesh.polygon(cell)).unwrap_or_else(|| point.clone()))
+      .collect();
+  }
+
+  points
+}
+
+// Centroid of a polygon, `None` when it has no area.
+fn centroid(polygon: &[Point]) -> Option<Point> {
+  let mut area = 0.0;
+  let mut sum = Point(0.0, 0.0);
+  for (index, point) in polygon.iter().enumerate() {
+    let next = &polygon[(index + 1) % polygon.len()];
+    let cross = point.0 * next.1 - next.0 * point.1;
+    area += cross;
+    sum = Point(sum.0 + (point.0 + next.0) * cross, sum.1 + (point.1 + next.1) * cross);
+  }
+
+  if area.abs() > f64::EPSILON {
+    Some(Point(sum.0 / (3.0 * area), sum.1 / (3.0 * area)))
+  } else {
+    None
+  }
+}
+
+// Uniform value in [0, 1) derived from the seed and an index (splitmix64), so
+// that a point gets the same perturbation whatever order points are visited in.
+pub fn indexed_random(seed: u64, index: u64) -> f64 {
+  let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+  z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+  z ^= z >> 31;
+  (z >> 11) as f64 / (1_u64 << 53) as f64
+}
+
 fn perturb_point_coord(value: f64, spacing: f64, chaos: f64, random: f64) -> f64 {
   let perturbation: f64 = random - 0.5_f64;
   let influence: f64 = perturbation / (1_f64 / chaos);
@@ -0,0 +1,57 @@
+use std::fmt;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+  Points,
+  Perturbation,
+  Elevation,
+  PostProcessing
+}
+
+impl Stage {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Stage::Points => "points",
+      Stage::Perturbation => "perturbation",
+      Stage::Elevation => "elevation",
+      Stage::PostProcessing => "post_processing"
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "generation cancelled")
+  }
+}
+
+impl std::error::Error for Cancelled {}
+
+// Receives the progress of a generation, from 0.0 to 1.0 within each stage,
+// between chunks of work. Returning `false` cancels the generation.
+pub trait Progress {
+  fn report(&mut self, stage: Stage, done: f64) -> bool;
+
+  fn check(&mut self, stage: Stage, done: f64) -> Result<(), Cancelled> {
+    if self.report(stage, done) { Ok(()) } else { Err(Cancelled) }
+  }
+}
+
+impl<F: FnMut(Stage, f64) -> bool> Progress for F {
+  fn report(&mut self, stage: Stage, done: f64) -> bool {
+    self(stage, done)
+  }
+}
+
+// Progress that is never reported anywhere and never cancels.
+pub struct Silent;
+
+impl Progress for Silent {
+  fn report(&mut self, _stage: Stage, _done: f64) -> bool {
+    true
+  }
+}
@@ -0,0 +1,233 @@
+extern crate rand;
+
+use rand::{Rng, rngs::StdRng};
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use crate::distance::Candidate;
+use crate::map::Point;
+use crate::mesh::{Mesh, distance};
+use crate::stage::{Draft, MapStage};
+
+/// Elevation above which land is too high to host a capital.
+const HABITABLE_ELEVATION: f64 = 0.5;
+
+/// Attempts at drawing a capital far enough from the others before giving up.
+const CAPITAL_ATTEMPTS: usize = 30;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct RegionOptions {
+  /// Number of capitals seeded over the land, hence of regions, at most.
+  pub count: usize,
+  /// Extra cost of a step across high ground, per unit of elevation, as a
+  /// multiple of its length.
+  pub elevation_cost: f64,
+  /// Extra cost of a step into or out of a river cell, as a multiple of its
+  /// length.
+  pub river_cost: f64
+}
+
+impl Default for RegionOptions {
+  fn default() -> Self {
+    RegionOptions { count: 8, elevation_cost: 8.0, river_cost: 4.0 }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Region {
+  pub id: usize,
+  pub capital: usize,
+  pub cells: usize,
+  /// Regions sharing a border with this one, by increasing id.
+  pub neighbors: Vec<usize>
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Border {
+  /// The two regions on each side of the border, the lowest id first.
+  pub regions: (usize, usize),
+  pub points: Vec<Point>
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Regions {
+  /// Region of every cell, `None` for water and land no capital reaches.
+  pub region_ids: Vec<Option<usize>>,
+  pub regions: Vec<Region>,
+  pub borders: Vec<Border>
+}
+
+pub struct RegionStage(pub RegionOptions);
+
+impl MapStage for RegionStage {
+  fn name(&self) -> &str {
+    "regions"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, random: &mut StdRng) {
+    let rivers = draft.layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+    let capitals = seed_capitals(random, draft.points, draft.elevation, draft.mesh, self.0.count);
+    let region_ids = grow_regions(draft.points, draft.elevation, rivers, draft.mesh, &capitals, &self.0);
+    draft.layers.regions = Some(describe(draft.mesh, region_ids, &capitals));
+  }
+}
+
+// Draws capitals on habitable land, keeping them apart so that regions get
+// comparable sizes.
+fn seed_capitals(random: &mut StdRng, points: &[Point], elevation: &[f64], mesh: &Mesh, count: usize) -> Vec<usize> {
+  let habitable: Vec<usize> = (0..points.len())
+    .filter(|cell| elevation[*cell] > 0.0 && elevation[*cell] < HABITABLE_ELEVATION)
+    .collect();
+  if habitable.is_empty() || count == 0 {
+    return Vec::new();
+  }
+
+  let land_area = habitable.len() as f64 * mesh.spacing * mesh.spacing;
+  let spread = (land_area / count as f64).sqrt() / 2.0;
+  let mut capitals: Vec<usize> = Vec::with_capacity(count);
+
+  for _ in 0..count * CAPITAL_ATTEMPTS {
+    if capitals.len() == count {
+      break;
+    }
+    let cell = habitable[random.gen_range(0, habitable.len())];
+    if capitals.iter().all(|capital| distance(&points[*capital], &points[cell]) >= spread) {
+      capitals.push(cell);
+    }
+  }
+
+  capitals
+}
+
+// Grows every region from its capital at once along the cheapest paths over
+// land, climbing and crossing rivers being more expensive than walking plains.
+fn grow_regions(
+  points: &[Point],
+  elevation: &[f64],
+  rivers: Option<&[bool]>,
+  mesh: &Mesh,
+  capitals: &[usize],
+  options: &RegionOptions
+) -> Vec<Option<usize>> {
+  let mut region_ids = vec![None; points.len()];
+  let mut costs = vec![f64::INFINITY; points.len()];
+  let mut heap = BinaryHeap::new();
+  let is_river = |cell: usize| rivers.is_some_and(|rivers| rivers[cell]);
+
+  for (id, capital) in capitals.iter().enumerate() {
+    region_ids[*capital] = Some(id);
+    costs[*capital] = 0.0;
+    heap.push(Candidate { cost: 0.0, cell: *capital });
+  }
+
+  while let Some(Candidate { cost, cell }) = heap.pop() {
+    if cost > costs[cell] {
+      continue;
+    }
+    for neighbor in mesh.neighbors(cell) {
+      if elevation[neighbor] <= 0.0 {
+        continue;
+      }
+      let climb = (elevation[neighbor] - elevation[cell]).max(0.0) + elevation[neighbor].max(0.0);
+      let river = if is_river(cell) != is_river(neighbor) { options.river_cost } else { 0.0 };
+      let next = cost + distance(&points[cell], &points[neighbor]) * (1.0 + climb * options.elevation_cost + river);
+      if next < costs[neighbor] {
+        costs[neighbor] = next;
+        region_ids[neighbor] = region_ids[cell];
+        heap.push(Candidate { cost: next, cell: neighbor });
+      }
+    }
+  }
+
+  region_ids
+}
+
+// Summarizes the regions and traces the borders between them. Shared by
+// generation and loading.
+pub fn describe(mesh: &Mesh, region_ids: Vec<Option<usize>>, capitals: &[usize]) -> Regions {
+  let mut regions: Vec<Region> = capitals
+    .iter()
+    .enumerate()
+    .map(|(id, capital)| Region { id, capital: *capital, cells: 0, neighbors: Vec::new() })
+    .collect();
+  for id in region_ids.iter().flatten() {
+    regions[*id].cells += 1;
+  }
+
+  let segments = find_segments(&region_ids, mesh);
+  for (a, b) in segments.keys() {
+    regions[*a].neighbors.push(*b);
+    regions[*b].neighbors.push(*a);
+  }
+  for region in regions.iter_mut() {
+    region.neighbors.sort_unstable();
+  }
+
+  let corners = &mesh.corners;
+  let borders = segments
+    .into_iter()
+    .flat_map(|(pair, segments)| {
+      chain(&segments)
+        .into_iter()
+        .map(|polyline| Border { regions: pair, points: polyline.iter().map(|corner| corners[*corner].clone()).collect() })
+        .collect::<Vec<Border>>()
+    })
+    .collect();
+
+  Regions { region_ids, regions, borders }
+}
+
+// Sides of the cells separating two regions, grouped by pair of regions.
+fn find_segments(region_ids: &[Option<usize>], mesh: &Mesh) -> BTreeMap<(usize, usize), Vec<(usize, usize)>> {
+  let mut segments: BTreeMap<(usize, usize), Vec<(usize, usize)>> = BTreeMap::new();
+
+  for cell in 0..mesh.len() {
+    let Some(id) = region_ids[cell] else { continue };
+    for side in mesh.sides(cell) {
+      // Each side is seen from both of its cells, only keep it once.
+      let Some(other) = side.neighbor.and_then(|neighbor| region_ids[neighbor]) else { continue };
+      if id < other {
+        segments.entry((id, other)).or_default().push((side.from, side.to));
+      }
+    }
+  }
+
+  segments
+}
+
+// Chains undirected segments into polylines of corner indices, starting from
+// the loose ends so that open borders come out in one piece.
+fn chain(segments: &[(usize, usize)]) -> Vec<Vec<usize>> {
+  let mut touching: HashMap<usize, Vec<usize>> = HashMap::new();
+  for (index, (from, to)) in segments.iter().enumerate() {
+    touching.entry(*from).or_default().push(index);
+    touching.entry(*to).or_default().push(index);
+  }
+
+  let mut starts: Vec<usize> = segments.iter().flat_map(|(from, to)| vec![*from, *to]).collect();
+  starts.sort_by_key(|corner| touching[corner].len().is_multiple_of(2));
+
+  let mut used = vec![false; segments.len()];
+  let mut polylines = Vec::new();
+
+  for start in starts {
+    let mut polyline = vec![start];
+    let mut corner = start;
+
+    while let Some(next) = touching[&corner].iter().find(|segment| !used[**segment]) {
+      used[*next] = true;
+      let (from, to) = segments[*next];
+      corner = if from == corner { to } else { from };
+      polyline.push(corner);
+    }
+
+    if polyline.len() > 1 {
+      polylines.push(polyline);
+    }
+  }
+
+  polylines
+}
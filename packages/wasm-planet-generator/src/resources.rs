@@ -0,0 +1,149 @@
+extern crate rand;
+
+use rand::{Rng, rngs::StdRng};
+use crate::climate::{self, Climate, ClimateOptions};
+use crate::distance::distance_field;
+use crate::elevation::Terrain;
+use crate::seasons::Koppen;
+use crate::stage::{Draft, MapStage};
+use crate::tectonics::Boundary;
+
+/// Elevation from which land counts as mountains, when the terrain isn't
+/// classified.
+const MOUNTAIN_ELEVATION: f64 = 0.4;
+
+/// Elevation down to which the sea floor counts as continental shelf, when the
+/// terrain isn't classified. The open ocean lies deeper.
+const SHELF_ELEVATION: f64 = -0.7;
+
+/// Highest elevation of sedimentary lowlands, and the steepest elevation
+/// difference they have with their neighbours.
+const LOWLAND_ELEVATION: f64 = 0.1;
+const LOWLAND_SLOPE: f64 = 0.03;
+
+/// Distance to a plate boundary, in cells, over which ore gets scarcer.
+const BOUNDARY_RANGE: f64 = 2.0;
+
+/// Moisture and mean annual temperature forests need, when the climate has no
+/// seasons to classify.
+const FOREST_MOISTURE: f64 = 0.5;
+const FOREST_TEMPERATURE: f64 = 0.0;
+
+/// Köppen classes of forested climates.
+const FORESTS: &[Koppen] = &[
+  Koppen::Af, Koppen::Am,
+  Koppen::Cfa, Koppen::Cfb, Koppen::Cfc, Koppen::Cwa, Koppen::Cwb, Koppen::Cwc, Koppen::Csb,
+  Koppen::Dfa, Koppen::Dfb, Koppen::Dfc, Koppen::Dfd, Koppen::Dwa, Koppen::Dwb, Koppen::Dwc, Koppen::Dwd
+];
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct ResourceOptions {
+  /// Probability for a cell fit for a resource to hold a deposit of it, by
+  /// resource.
+  pub ore: f64,
+  pub oil: f64,
+  pub fish: f64,
+  pub timber: f64
+}
+
+impl Default for ResourceOptions {
+  fn default() -> Self {
+    ResourceOptions { ore: 0.3, oil: 0.1, fish: 0.4, timber: 0.6 }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Resource {
+  Ore,
+  Oil,
+  Fish,
+  Timber
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Deposit {
+  pub resource: Resource,
+  /// From 0.0 to 1.0.
+  pub richness: f64
+}
+
+// Places deposits from the geology and climate of every cell: ore in the
+// mountains and along plate boundaries, oil under sedimentary lowlands and
+// continental shelves, fish over the shelves and timber in forests. The
+// climate defaults to that of `ClimateOptions::default()` when disabled.
+pub struct ResourceStage(pub ResourceOptions);
+
+impl MapStage for ResourceStage {
+  fn name(&self) -> &str {
+    "resources"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, random: &mut StdRng) {
+    let fallback;
+    let climate = match &draft.layers.climate {
+      Some(climate) => climate,
+      None => {
+        let rivers = draft.layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+        let options = ClimateOptions { seasons: true, ..ClimateOptions::default() };
+        fallback = climate::simulate(draft.points, draft.elevation, rivers, draft.mesh, draft.temperature_offset, &options);
+        &fallback
+      }
+    };
+    draft.layers.resources = Some(place(draft, climate, &self.0, random));
+  }
+}
+
+// Every resource a cell is fit for is found there with the probability set
+// in the options, ore the more likely the closer to a plate boundary.
+fn place(draft: &Draft, climate: &Climate, options: &ResourceOptions, random: &mut StdRng) -> Vec<Vec<Deposit>> {
+  let elevation = draft.elevation;
+  let spacing = draft.mesh.spacing.max(f64::EPSILON);
+  let near_boundary: Vec<f64> = match draft.tectonics {
+    Some(tectonics) => {
+      let sources = (0..draft.len()).filter(|cell| tectonics.boundaries[*cell] != Boundary::None);
+      distance_field(draft.points, draft.mesh, sources)
+        .into_iter()
+        .map(|distance| (-distance / (BOUNDARY_RANGE * spacing)).exp())
+        .collect()
+    },
+    None => vec![0.0; draft.len()]
+  };
+
+  (0..draft.len())
+    .map(|cell| {
+      let terrain = draft.terrain.map(|terrain| terrain[cell]);
+      let land = elevation[cell] > 0.0;
+      let mountain = land && terrain.map_or(elevation[cell] >= MOUNTAIN_ELEVATION, |terrain| terrain == Terrain::Mountain);
+      let shelf = !land && terrain.map_or(elevation[cell] >= SHELF_ELEVATION, |terrain| terrain == Terrain::Shelf);
+      let lowland = land && elevation[cell] <= LOWLAND_ELEVATION && draft.neighbors(cell)
+        .iter()
+        .all(|neighbor| (elevation[*neighbor].max(0.0) - elevation[cell]).abs() <= LOWLAND_SLOPE);
+      let forest = land && match climate.seasons.as_ref().and_then(|seasons| seasons.koppen[cell]) {
+        Some(koppen) => FORESTS.contains(&koppen),
+        None => climate.moisture[cell] >= FOREST_MOISTURE && climate.temperature[cell] >= FOREST_TEMPERATURE
+      };
+
+      let fitness = [
+        (Resource::Ore, options.ore * if mountain { 1.0 } else if land { near_boundary[cell] } else { 0.0 }),
+        (Resource::Oil, options.oil * if lowland || shelf { 1.0 } else { 0.0 }),
+        (Resource::Fish, options.fish * if shelf { 1.0 } else { 0.0 }),
+        (Resource::Timber, options.timber * if forest { 1.0 } else { 0.0 })
+      ];
+      // Draws for every resource, fit or not, so that a resource doesn't
+      // shift the deposits of the others.
+      fitness
+        .iter()
+        .filter_map(|(resource, probability)| {
+          let (draw, richness) = (random.gen::<f64>(), random.gen::<f64>());
+          if draw < *probability { Some(Deposit { resource: *resource, richness }) } else { None }
+        })
+        .collect()
+    })
+    .collect()
+}
@@ -0,0 +1,219 @@
+extern crate rand;
+
+use rand::rngs::StdRng;
+use std::collections::{BinaryHeap, HashSet};
+use crate::distance::Candidate;
+use crate::elevation::Terrain;
+use crate::map::Point;
+use crate::mesh::{Mesh, distance};
+use crate::settlements::{Settlement, Tier};
+use crate::stage::{Draft, MapStage};
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct RoadOptions {
+  /// Extra cost of a step, per unit of elevation climbed or descended, as a
+  /// multiple of its length.
+  pub slope_cost: f64,
+  /// Extra cost of a step into rough terrain, as a multiple of its length.
+  /// Only applies when the terrain is classified.
+  pub terrain_cost: f64,
+  /// Extra cost of a step into or out of a river cell, as a multiple of its
+  /// length.
+  pub river_cost: f64,
+  /// Cost of a step along an existing road, as a multiple of the cost of
+  /// building it, so that roads merge rather than run side by side.
+  pub reuse_cost: f64,
+  /// Links settlements on the coast by sea when it is cheaper than by land,
+  /// or when there is no way by land.
+  pub sea_routes: bool,
+  /// Cost of a step at sea, as a multiple of its length.
+  pub sea_cost: f64,
+  /// Extra cost of boarding or leaving a ship, in map units.
+  pub port_cost: f64
+}
+
+impl Default for RoadOptions {
+  fn default() -> Self {
+    RoadOptions {
+      slope_cost: 20.0,
+      terrain_cost: 1.0,
+      river_cost: 2.0,
+      reuse_cost: 0.5,
+      sea_routes: true,
+      sea_cost: 1.0,
+      port_cost: 100.0
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum RoadClass {
+  Highway,
+  Road,
+  Track,
+  Sea
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Road {
+  pub class: RoadClass,
+  /// Cells the road goes through, from one end to the other.
+  pub cells: Vec<usize>,
+  pub points: Vec<Point>
+}
+
+pub struct RoadStage(pub RoadOptions);
+
+impl MapStage for RoadStage {
+  fn name(&self) -> &str {
+    "roads"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, _random: &mut StdRng) {
+    let settlements = match &draft.layers.settlements {
+      Some(settlements) => settlements,
+      None => return
+    };
+    let rivers = draft.layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+    let roads = build(draft.points, draft.elevation, rivers, draft.terrain, draft.mesh, settlements, &self.0);
+    draft.layers.roads = Some(roads);
+  }
+}
+
+// Extra cost of crossing a kind of terrain, relative to plains.
+fn roughness(terrain: Terrain) -> f64 {
+  match terrain {
+    Terrain::Hill => 0.5,
+    Terrain::Badlands => 1.0,
+    Terrain::Mountain => 2.0,
+    _ => 0.0
+  }
+}
+
+// Links every settlement, from the most important one down, to the cheapest
+// to reach of the settlements linked before it. Only the stretches that don't
+// follow an existing road are built, and their class follows the tier of the
+// settlement they were built for.
+pub fn build(
+  points: &[Point],
+  elevation: &[f64],
+  rivers: Option<&[bool]>,
+  terrain: Option<&[Terrain]>,
+  mesh: &Mesh,
+  settlements: &[Settlement],
+  options: &RoadOptions
+) -> Vec<Road> {
+  let ports: HashSet<usize> = settlements
+    .iter()
+    .map(|settlement| settlement.cell)
+    .filter(|cell| mesh.neighbors(*cell).iter().any(|neighbor| elevation[*neighbor] <= 0.0))
+    .collect();
+  let is_river = |cell: usize| rivers.is_some_and(|rivers| rivers[cell]);
+
+  // Cost of a step between two neighbouring cells, `None` when there is no
+  // way through.
+  let step = |from: usize, to: usize| -> Option<f64> {
+    let length = distance(&points[from], &points[to]);
+    match (elevation[from] > 0.0, elevation[to] > 0.0) {
+      (true, true) => {
+        let slope = (elevation[to] - elevation[from]).abs();
+        let rough = terrain.map_or(0.0, |terrain| roughness(terrain[to]));
+        let river = if is_river(from) != is_river(to) { options.river_cost } else { 0.0 };
+        Some(length * (1.0 + slope * options.slope_cost + rough * options.terrain_cost + river))
+      },
+      (false, false) if options.sea_routes => Some(length * options.sea_cost),
+      (true, false) if options.sea_routes && ports.contains(&from) => Some(length * options.sea_cost + options.port_cost),
+      (false, true) if options.sea_routes && ports.contains(&to) => Some(length * options.sea_cost + options.port_cost),
+      _ => None
+    }
+  };
+
+  let mut built: HashSet<(usize, usize)> = HashSet::new();
+  let mut roads = Vec::new();
+
+  for (index, settlement) in settlements.iter().enumerate().skip(1) {
+    let targets: HashSet<usize> = settlements[..index].iter().map(|other| other.cell).collect();
+    let path = match cheapest_path(points.len(), settlement.cell, &targets, mesh, |from, to| {
+      let edge = (from.min(to), from.max(to));
+      step(from, to).map(|cost| if built.contains(&edge) { cost * options.reuse_cost } else { cost })
+    }) {
+      Some(path) => path,
+      None => continue
+    };
+
+    let class = match settlement.tier {
+      Tier::Capital | Tier::City => RoadClass::Highway,
+      Tier::Town => RoadClass::Road,
+      Tier::Village => RoadClass::Track
+    };
+    let mut current: Option<Road> = None;
+    for pair in path.windows(2) {
+      let edge = (pair[0].min(pair[1]), pair[0].max(pair[1]));
+      let edge_class = if elevation[pair[0]] > 0.0 && elevation[pair[1]] > 0.0 { class } else { RoadClass::Sea };
+      if !built.insert(edge) {
+        roads.extend(current.take());
+        continue;
+      }
+      match current.as_mut() {
+        Some(road) if road.class == edge_class => road.cells.push(pair[1]),
+        _ => {
+          roads.extend(current.take());
+          current = Some(Road { class: edge_class, cells: vec![pair[0], pair[1]], points: Vec::new() });
+        }
+      }
+    }
+    roads.extend(current);
+  }
+
+  for road in roads.iter_mut() {
+    road.points = road.cells.iter().map(|cell| points[*cell].clone()).collect();
+  }
+  roads
+}
+
+// Cells along the cheapest path from `source` to the closest of `targets`,
+// both included.
+fn cheapest_path(
+  count: usize,
+  source: usize,
+  targets: &HashSet<usize>,
+  mesh: &Mesh,
+  step: impl Fn(usize, usize) -> Option<f64>
+) -> Option<Vec<usize>> {
+  let mut costs = vec![f64::INFINITY; count];
+  let mut previous: Vec<Option<usize>> = vec![None; count];
+  let mut heap = BinaryHeap::new();
+  costs[source] = 0.0;
+  heap.push(Candidate { cost: 0.0, cell: source });
+
+  while let Some(Candidate { cost, cell }) = heap.pop() {
+    if cost > costs[cell] {
+      continue;
+    }
+    if targets.contains(&cell) {
+      let mut path = vec![cell];
+      while let Some(before) = previous[*path.last().unwrap()] {
+        path.push(before);
+      }
+      path.reverse();
+      return Some(path);
+    }
+    for neighbor in mesh.neighbors(cell) {
+      let Some(length) = step(cell, neighbor) else { continue };
+      let next = cost + length;
+      if next < costs[neighbor] {
+        costs[neighbor] = next;
+        previous[neighbor] = Some(cell);
+        heap.push(Candidate { cost: next, cell: neighbor });
+      }
+    }
+  }
+
+  None
+}
@@ -0,0 +1,804 @@
+// Versioned binary format for generated maps.
+//
+// A save starts with a header: the `PGEN` magic, the format version, the
+// version of the crate that wrote it, then the seed, the map parameters and
+// the generation options (as JSON). Layers follow as chunks, each made of a
+// tag, a byte length and a payload. Readers skip chunks with an unknown tag,
+// so new layers can be added without bumping the format version.
+//
+// Points and elevations are the only mandatory layers: everything else either
+// is its own chunk or is derived again from the elevation on load. Names are
+// saved as they are, so that renamed features stay renamed. The rest of
+// the map metadata (elevation parameters and stage timings) is an optional JSON
+// chunk, maps saved without it are given the parameters of the current crate.
+
+extern crate serde_json;
+
+use std::collections::BTreeMap;
+use std::fmt;
+use crate::map::{Map, Point, seed_to_u64};
+use crate::mesh::{self, Mesh};
+use crate::meta::{Meta, Timing};
+use crate::names::Names;
+use crate::elevation::ElevationParameters;
+use crate::options::Options;
+use crate::hydrology::Hydrology;
+use crate::ice::{Ice, IceKind};
+use crate::circulation::Circulation;
+use crate::climate::{self, Climate};
+use crate::elevation::Terrain;
+use crate::stage::Layers;
+use crate::regions;
+use crate::resources::{Deposit, Resource};
+use crate::seasons::{self, MONTHS};
+use crate::settlements::{Settlement, Tier};
+use crate::roads::{Road, RoadClass};
+use crate::tectonics::{Boundary, Plate, PlateKind, Tectonics};
+
+const MAGIC: &[u8; 4] = b"PGEN";
+
+/// Version of the binary format. Saves written with a greater version can't
+/// be read by this version of the crate.
+pub const FORMAT_VERSION: u16 = 1;
+
+const POINTS_CHUNK: u8 = 1;
+const ELEVATION_CHUNK: u8 = 2;
+const TECTONICS_CHUNK: u8 = 3;
+const HYDROLOGY_CHUNK: u8 = 4;
+const META_CHUNK: u8 = 5;
+const CLIMATE_CHUNK: u8 = 6;
+const LAYERS_CHUNK: u8 = 7;
+const TERRAIN_CHUNK: u8 = 8;
+const REGIONS_CHUNK: u8 = 9;
+const SETTLEMENTS_CHUNK: u8 = 10;
+const ROADS_CHUNK: u8 = 11;
+const CIRCULATION_CHUNK: u8 = 12;
+const SEASONS_CHUNK: u8 = 13;
+const ICE_CHUNK: u8 = 14;
+const RESOURCES_CHUNK: u8 = 15;
+const NAMES_CHUNK: u8 = 16;
+
+const RAW_ENCODING: u8 = 0;
+const QUANTIZED_ENCODING: u8 = 1;
+
+#[derive(Deserialize, Serialize, Clone, Default)]
+#[serde(default)]
+pub struct SaveOptions {
+  /// Stores floating point layers as `f32` rather than `f64`, halving their
+  /// size at the expense of precision.
+  pub quantize: bool
+}
+
+#[derive(Serialize, Clone)]
+pub struct Header {
+  pub format_version: u16,
+  pub generator_version: String,
+  pub seed: String,
+  pub width: f64,
+  pub height: f64,
+  pub spacing: f64,
+  pub chaos: f64,
+  pub options: Options
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LoadError {
+  InvalidMagic,
+  IncompatibleVersion { found: u16, supported: u16 },
+  Truncated,
+  MissingLayer(&'static str),
+  InvalidData(String)
+}
+
+impl Header {
+  // Header of a map written by this version of the crate.
+  pub fn new(meta: &Meta) -> Self {
+    Header {
+      format_version: FORMAT_VERSION,
+      generator_version: env!("CARGO_PKG_VERSION").to_string(),
+      seed: meta.seed.clone(),
+      width: meta.width,
+      height: meta.height,
+      spacing: meta.spacing,
+      chaos: meta.chaos,
+      options: meta.options.clone()
+    }
+  }
+}
+
+impl fmt::Display for LoadError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      LoadError::InvalidMagic => write!(f, "not a planet generator save"),
+      LoadError::IncompatibleVersion { found, supported } => write!(
+        f,
+        "save format version {} is not supported, this generator reads up to version {}",
+        found, supported
+      ),
+      LoadError::Truncated => write!(f, "save is truncated"),
+      LoadError::MissingLayer(layer) => write!(f, "save has no {} layer", layer),
+      LoadError::InvalidData(message) => write!(f, "save is invalid: {}", message)
+    }
+  }
+}
+
+impl std::error::Error for LoadError {}
+
+// Part of the metadata that isn't in the header.
+#[derive(Serialize, Deserialize)]
+struct Extra {
+  elevation: ElevationParameters,
+  timings: Vec<Timing>
+}
+
+// The header is built from the map metadata, so it can't disagree with the
+// layers.
+pub fn write(map: &Map, save_options: &SaveOptions) -> Vec<u8> {
+  let meta = &map.meta;
+  let header = Header::new(meta);
+  let mut writer = Writer::default();
+
+  writer.bytes.extend_from_slice(MAGIC);
+  writer.u16(header.format_version);
+  writer.string(&header.generator_version);
+  writer.string(&header.seed);
+  writer.f64(header.width);
+  writer.f64(header.height);
+  writer.f64(header.spacing);
+  writer.f64(header.chaos);
+  writer.string(&serde_json::to_string(&header.options).unwrap());
+
+  let coords: Vec<f64> = map.points.iter().flat_map(|point| vec![point.0, point.1]).collect();
+  writer.chunk(POINTS_CHUNK, |chunk| chunk.floats(&coords, save_options.quantize));
+  writer.chunk(ELEVATION_CHUNK, |chunk| chunk.floats(&map.elevation, save_options.quantize));
+
+  if let Some(tectonics) = &map.tectonics {
+    writer.chunk(TECTONICS_CHUNK, |chunk| {
+      chunk.u32(tectonics.plates.len() as u32);
+      for plate in tectonics.plates.iter() {
+        chunk.u8(match plate.kind { PlateKind::Oceanic => 0, PlateKind::Continental => 1 });
+        chunk.u32(plate.origin as u32);
+        chunk.f64(plate.velocity.0);
+        chunk.f64(plate.velocity.1);
+      }
+      chunk.indices(&tectonics.plate_ids.iter().map(|id| Some(*id)).collect::<Vec<_>>());
+      chunk.u32(tectonics.boundaries.len() as u32);
+      for boundary in tectonics.boundaries.iter() {
+        chunk.u8(boundary_code(*boundary));
+      }
+      chunk.floats(&tectonics.uplift, save_options.quantize);
+    });
+  }
+
+  if let Some(hydrology) = &map.hydrology {
+    writer.chunk(HYDROLOGY_CHUNK, |chunk| {
+      chunk.indices(&hydrology.downstream);
+      chunk.floats(&hydrology.flux, save_options.quantize);
+      chunk.u32(hydrology.rivers.len() as u32);
+      for river in hydrology.rivers.iter() {
+        chunk.u8(*river as u8);
+      }
+    });
+  }
+
+  if let Some(circulation) = &map.circulation {
+    writer.chunk(CIRCULATION_CHUNK, |chunk| {
+      for vectors in [&circulation.wind, &circulation.currents] {
+        let coords: Vec<f64> = vectors.iter().flat_map(|vector| vec![vector.0, vector.1]).collect();
+        chunk.floats(&coords, save_options.quantize);
+      }
+    });
+  }
+
+  if let Some(climate) = &map.climate {
+    writer.chunk(CLIMATE_CHUNK, |chunk| {
+      chunk.floats(&climate.temperature, save_options.quantize);
+      chunk.floats(&climate.moisture, save_options.quantize);
+    });
+    if let Some(seasons) = &climate.seasons {
+      writer.chunk(SEASONS_CHUNK, |chunk| {
+        for series in [&seasons.temperature, &seasons.precipitation] {
+          chunk.floats(&series.iter().flatten().cloned().collect::<Vec<f64>>(), save_options.quantize);
+        }
+      });
+    }
+  }
+
+  if let Some(ice) = &map.ice {
+    writer.chunk(ICE_CHUNK, |chunk| {
+      chunk.u32(ice.cells.len() as u32);
+      for kind in ice.cells.iter() {
+        chunk.u8(kind.map_or(0, |kind| kind as u8 + 1));
+      }
+      chunk.u32(ice.glaciers.len() as u32);
+      for glacier in ice.glaciers.iter() {
+        chunk.indices(&glacier.iter().map(|cell| Some(*cell)).collect::<Vec<_>>());
+      }
+    });
+  }
+
+  if let Some(resources) = &map.resources {
+    writer.chunk(RESOURCES_CHUNK, |chunk| {
+      chunk.u32(resources.len() as u32);
+      for deposits in resources.iter() {
+        chunk.u8(deposits.len() as u8);
+        for deposit in deposits.iter() {
+          chunk.u8(deposit.resource as u8);
+          chunk.f64(deposit.richness);
+        }
+      }
+    });
+  }
+
+  if let Some(terrain) = &map.terrain {
+    writer.chunk(TERRAIN_CHUNK, |chunk| {
+      chunk.u32(terrain.len() as u32);
+      for value in terrain.iter() {
+        chunk.u8(terrain_code(*value));
+      }
+    });
+  }
+
+  if let Some(regions) = &map.regions {
+    writer.chunk(REGIONS_CHUNK, |chunk| {
+      chunk.indices(&regions.regions.iter().map(|region| Some(region.capital)).collect::<Vec<_>>());
+      chunk.indices(&regions.region_ids);
+    });
+  }
+
+  if let Some(settlements) = &map.settlements {
+    writer.chunk(SETTLEMENTS_CHUNK, |chunk| {
+      chunk.u32(settlements.len() as u32);
+      for settlement in settlements.iter() {
+        chunk.u32(settlement.cell as u32);
+        chunk.u8(settlement.tier as u8);
+        chunk.f64(settlement.score);
+      }
+    });
+  }
+
+  if let Some(roads) = &map.roads {
+    writer.chunk(ROADS_CHUNK, |chunk| {
+      chunk.u32(roads.len() as u32);
+      for road in roads.iter() {
+        chunk.u8(road.class as u8);
+        chunk.u32(road.cells.len() as u32);
+        for cell in road.cells.iter() {
+          chunk.u32(*cell as u32);
+        }
+      }
+    });
+  }
+
+  if let Some(names) = &map.names {
+    writer.chunk(NAMES_CHUNK, |chunk| chunk.string(&serde_json::to_string(names).unwrap()));
+  }
+
+  for (name, values) in map.layers.iter() {
+    writer.chunk(LAYERS_CHUNK, |chunk| {
+      chunk.string(name);
+      chunk.floats(values, save_options.quantize);
+    });
+  }
+
+  writer.chunk(META_CHUNK, |chunk| {
+    let extra = Extra { elevation: meta.elevation.clone(), timings: meta.timings.clone() };
+    chunk.string(&serde_json::to_string(&extra).unwrap());
+  });
+
+  writer.bytes
+}
+
+pub fn read(bytes: &[u8]) -> Result<(Header, Map), LoadError> {
+  let mut reader = Reader { bytes, offset: 0 };
+
+  if reader.take(MAGIC.len())? != MAGIC {
+    return Err(LoadError::InvalidMagic);
+  }
+  let format_version = reader.u16()?;
+  if format_version > FORMAT_VERSION {
+    return Err(LoadError::IncompatibleVersion { found: format_version, supported: FORMAT_VERSION });
+  }
+
+  let header = Header {
+    format_version,
+    generator_version: reader.string()?,
+    seed: reader.string()?,
+    width: reader.f64()?,
+    height: reader.f64()?,
+    spacing: reader.f64()?,
+    chaos: reader.f64()?,
+    options: serde_json::from_str(&reader.string()?)
+      .map_err(|error| LoadError::InvalidData(error.to_string()))?
+  };
+
+  let mut points = None;
+  let mut elevation = None;
+  let mut tectonics = None;
+  let mut hydrology = None;
+  let mut circulation = None;
+  let mut climate = None;
+  let mut seasons = None;
+  let mut ice = None;
+  let mut resources = None;
+  let mut named = BTreeMap::new();
+  let mut terrain = None;
+  let mut regions = None;
+  let mut settlements = None;
+  let mut roads = None;
+  let mut names = None;
+  let mut extra = None;
+
+  while reader.offset < bytes.len() {
+    let tag = reader.u8()?;
+    let length = reader.u32()? as usize;
+    let mut chunk = Reader { bytes: reader.take(length)?, offset: 0 };
+
+    match tag {
+      POINTS_CHUNK => {
+        points = Some(pairs(chunk.floats()?)?);
+      },
+      ELEVATION_CHUNK => elevation = Some(chunk.floats()?),
+      TECTONICS_CHUNK => {
+        let count = chunk.u32()? as usize;
+        let mut plates = Vec::new();
+        for _ in 0..count {
+          let kind = match chunk.u8()? {
+            0 => PlateKind::Oceanic,
+            1 => PlateKind::Continental,
+            kind => return Err(LoadError::InvalidData(format!("unknown plate kind {}", kind)))
+          };
+          let origin = chunk.u32()? as usize;
+          let velocity = Point(chunk.f64()?, chunk.f64()?);
+          plates.push(Plate { kind, origin, velocity });
+        }
+        let plate_ids = chunk.indices()?
+          .into_iter()
+          .map(|id| id.ok_or_else(|| LoadError::InvalidData("cell without plate".to_string())))
+          .collect::<Result<Vec<usize>, LoadError>>()?;
+        let count = chunk.u32()? as usize;
+        let mut boundaries = Vec::new();
+        for _ in 0..count {
+          boundaries.push(boundary_from_code(chunk.u8()?)?);
+        }
+        let uplift = chunk.floats()?;
+        tectonics = Some(Tectonics { plates, plate_ids, boundaries, uplift });
+      },
+      HYDROLOGY_CHUNK => {
+        let downstream = chunk.indices()?;
+        let flux = chunk.floats()?;
+        let count = chunk.u32()? as usize;
+        let mut rivers = Vec::new();
+        for _ in 0..count {
+          rivers.push(chunk.u8()? != 0);
+        }
+        hydrology = Some(Hydrology { downstream, flux, rivers });
+      },
+      CIRCULATION_CHUNK => {
+        let wind = pairs(chunk.floats()?)?;
+        circulation = Some(Circulation { wind, currents: pairs(chunk.floats()?)? });
+      },
+      CLIMATE_CHUNK => climate = Some(Climate { temperature: chunk.floats()?, moisture: chunk.floats()?, seasons: None }),
+      SEASONS_CHUNK => {
+        let mut series = || -> Result<Vec<[f64; MONTHS]>, LoadError> {
+          let values = chunk.floats()?;
+          if values.len() % MONTHS != 0 {
+            return Err(LoadError::InvalidData("seasons must have twelve months".to_string()));
+          }
+          Ok(values.chunks(MONTHS).map(|months| {
+            let mut array = [0.0; MONTHS];
+            array.copy_from_slice(months);
+            array
+          }).collect())
+        };
+        seasons = Some((series()?, series()?));
+      },
+      ICE_CHUNK => {
+        let count = chunk.u32()? as usize;
+        let mut cells = Vec::new();
+        for _ in 0..count {
+          cells.push(match chunk.u8()? {
+            0 => None,
+            1 => Some(IceKind::Sheet),
+            2 => Some(IceKind::Sea),
+            3 => Some(IceKind::Glacier),
+            kind => return Err(LoadError::InvalidData(format!("unknown ice {}", kind)))
+          });
+        }
+        let count = chunk.u32()? as usize;
+        let mut glaciers = Vec::new();
+        for _ in 0..count {
+          let glacier = chunk.indices()?
+            .into_iter()
+            .map(|cell| cell.ok_or_else(|| LoadError::InvalidData("glacier without cell".to_string())))
+            .collect::<Result<Vec<usize>, LoadError>>()?;
+          glaciers.push(glacier);
+        }
+        ice = Some(Ice { cells, glaciers });
+      },
+      RESOURCES_CHUNK => {
+        let count = chunk.u32()? as usize;
+        let mut values = Vec::new();
+        for _ in 0..count {
+          let mut deposits = Vec::new();
+          for _ in 0..chunk.u8()? {
+            let resource = match chunk.u8()? {
+              0 => Resource::Ore,
+              1 => Resource::Oil,
+              2 => Resource::Fish,
+              3 => Resource::Timber,
+              resource => return Err(LoadError::InvalidData(format!("unknown resource {}", resource)))
+            };
+            deposits.push(Deposit { resource, richness: chunk.f64()? });
+          }
+          values.push(deposits);
+        }
+        resources = Some(values);
+      },
+      TERRAIN_CHUNK => {
+        let count = chunk.u32()? as usize;
+        let mut values = Vec::new();
+        for _ in 0..count {
+          values.push(terrain_from_code(chunk.u8()?)?);
+        }
+        terrain = Some(values);
+      },
+      REGIONS_CHUNK => {
+        let capitals = chunk.indices()?
+          .into_iter()
+          .map(|capital| capital.ok_or_else(|| LoadError::InvalidData("region without capital".to_string())))
+          .collect::<Result<Vec<usize>, LoadError>>()?;
+        regions = Some((capitals, chunk.indices()?));
+      },
+      SETTLEMENTS_CHUNK => {
+        let count = chunk.u32()? as usize;
+        let mut values = Vec::new();
+        for _ in 0..count {
+          let cell = chunk.u32()? as usize;
+          let tier = match chunk.u8()? {
+            0 => Tier::Capital,
+            1 => Tier::City,
+            2 => Tier::Town,
+            3 => Tier::Village,
+            tier => return Err(LoadError::InvalidData(format!("unknown settlement tier {}", tier)))
+          };
+          values.push((cell, tier, chunk.f64()?));
+        }
+        settlements = Some(values);
+      },
+      ROADS_CHUNK => {
+        let count = chunk.u32()? as usize;
+        let mut values = Vec::new();
+        for _ in 0..count {
+          let class = match chunk.u8()? {
+            0 => RoadClass::Highway,
+            1 => RoadClass::Road,
+            2 => RoadClass::Track,
+            3 => RoadClass::Sea,
+            class => return Err(LoadError::InvalidData(format!("unknown road class {}", class)))
+          };
+          let length = chunk.u32()? as usize;
+          let mut cells = Vec::new();
+          for _ in 0..length {
+            cells.push(chunk.u32()? as usize);
+          }
+          values.push((class, cells));
+        }
+        roads = Some(values);
+      },
+      LAYERS_CHUNK => {
+        let name = chunk.string()?;
+        named.insert(name, chunk.floats()?);
+      },
+      NAMES_CHUNK => names = Some(
+        serde_json::from_str::<Names>(&chunk.string()?).map_err(|error| LoadError::InvalidData(error.to_string()))?
+      ),
+      META_CHUNK => extra = Some(
+        serde_json::from_str::<Extra>(&chunk.string()?).map_err(|error| LoadError::InvalidData(error.to_string()))?
+      ),
+      _ => {}
+    }
+  }
+
+  let points = points.ok_or(LoadError::MissingLayer("points"))?;
+  let elevation = elevation.ok_or(LoadError::MissingLayer("elevation"))?;
+  let (cols, rows) = mesh::lattice(header.width, header.height, header.spacing);
+  if points.len() != cols * rows || elevation.len() != cols * rows {
+    return Err(LoadError::InvalidData(format!(
+      "expected {} cells for the map parameters, found {} points and {} elevations",
+      cols * rows, points.len(), elevation.len()
+    )));
+  }
+  let mesh = Mesh::new(&points, header.width, header.height, header.spacing);
+
+  // Layers are checked against the map before anything indexes them.
+  let cells = mesh.len();
+  if let Some(tectonics) = &tectonics {
+    if tectonics.plate_ids.len() != cells
+      || tectonics.boundaries.len() != cells
+      || tectonics.uplift.len() != cells
+      || tectonics.plate_ids.iter().any(|id| *id >= tectonics.plates.len())
+      || tectonics.plates.iter().any(|plate| plate.origin >= cells) {
+      return Err(LoadError::InvalidData("tectonics don't match the map".to_string()));
+    }
+  }
+  if let Some(hydrology) = &hydrology {
+    if hydrology.downstream.len() != cells
+      || hydrology.flux.len() != cells
+      || hydrology.rivers.len() != cells
+      || hydrology.downstream.iter().flatten().any(|cell| *cell >= cells) {
+      return Err(LoadError::InvalidData("hydrology doesn't match the map".to_string()));
+    }
+  }
+  if let Some(climate) = &climate {
+    if climate.temperature.len() != cells || climate.moisture.len() != cells {
+      return Err(LoadError::InvalidData("climate doesn't match the map".to_string()));
+    }
+  }
+  if terrain.as_ref().is_some_and(|terrain: &Vec<Terrain>| terrain.len() != cells) {
+    return Err(LoadError::InvalidData("terrain doesn't match the map".to_string()));
+  }
+  if let Some((name, _)) = named.iter().find(|(_, values)| values.len() != cells) {
+    return Err(LoadError::InvalidData(format!("layer {} doesn't match the map", name)));
+  }
+
+  let extra = extra.unwrap_or_else(|| Extra { elevation: ElevationParameters::default(), timings: Vec::new() });
+  let meta = Meta {
+    generator_version: header.generator_version.clone(),
+    seed: header.seed.clone(),
+    seed_value: seed_to_u64(&header.seed),
+    width: header.width,
+    height: header.height,
+    spacing: header.spacing,
+    chaos: header.chaos,
+    options: header.options.clone(),
+    elevation: extra.elevation,
+    timings: extra.timings
+  };
+
+  if let Some(ice) = &ice {
+    if ice.cells.len() != mesh.len() || ice.glaciers.iter().flatten().any(|cell| *cell >= mesh.len()) {
+      return Err(LoadError::InvalidData("ice doesn't match the map".to_string()));
+    }
+  }
+  if resources.as_ref().is_some_and(|resources: &Vec<Vec<Deposit>>| resources.len() != mesh.len()) {
+    return Err(LoadError::InvalidData("resources don't match the map".to_string()));
+  }
+  if let Some(circulation) = &circulation {
+    if circulation.wind.len() != mesh.len() || circulation.currents.len() != mesh.len() {
+      return Err(LoadError::InvalidData("circulation doesn't match the map".to_string()));
+    }
+  }
+  if let (Some(climate), Some((temperature, precipitation))) = (climate.as_mut(), seasons) {
+    if temperature.len() != mesh.len() || precipitation.len() != mesh.len() {
+      return Err(LoadError::InvalidData("seasons don't match the map".to_string()));
+    }
+    let latitudes: Vec<f64> = points.iter().map(|point| climate::latitude(point, &mesh)).collect();
+    climate.seasons = Some(seasons::describe(&latitudes, &elevation, temperature, precipitation));
+  }
+  if let Some((capitals, region_ids)) = &regions {
+    if region_ids.len() != mesh.len()
+      || capitals.iter().any(|capital| *capital >= mesh.len())
+      || region_ids.iter().flatten().any(|id| *id >= capitals.len()) {
+      return Err(LoadError::InvalidData("regions don't match the map".to_string()));
+    }
+  }
+  let regions = regions.map(|(capitals, region_ids)| regions::describe(&mesh, region_ids, &capitals));
+  let settlements = match settlements {
+    Some(settlements) => Some(
+      settlements
+        .into_iter()
+        .map(|(cell, tier, score)| match points.get(cell) {
+          Some(position) => Ok(Settlement { cell, position: position.clone(), tier, score }),
+          None => Err(LoadError::InvalidData(format!("settlement on missing cell {}", cell)))
+        })
+        .collect::<Result<Vec<Settlement>, LoadError>>()?
+    ),
+    None => None
+  };
+  if let Some(roads) = &roads {
+    if roads.iter().flat_map(|(_, cells)| cells.iter()).any(|cell| *cell >= mesh.len()) {
+      return Err(LoadError::InvalidData("roads don't match the map".to_string()));
+    }
+  }
+  let roads = roads.map(|roads| {
+    roads
+      .into_iter()
+      .map(|(class, cells)| Road { class, points: cells.iter().map(|cell| points[*cell].clone()).collect(), cells })
+      .collect()
+  });
+  let layers = Layers { hydrology, circulation, climate, ice, resources, regions, settlements, roads, named };
+  let mut map = Map::assemble(points, elevation, tectonics, layers, &mesh, meta, &mut |_| true)
+    .expect("loading can't be cancelled");
+  map.terrain = terrain;
+
+  if let Some(names) = names {
+    if names.landmasses.len() != map.landmasses.len()
+      || names.water_body_ids.len() != cells
+      || names.water_body_ids.iter().flatten().any(|id| *id >= names.water_bodies.len())
+      || names.rivers.iter().any(|river| river.mouth >= cells)
+      || names.settlements.len() != map.settlements.as_ref().map_or(0, |settlements| settlements.len()) {
+      return Err(LoadError::InvalidData("names don't match the map".to_string()));
+    }
+    map.names = Some(names);
+  }
+
+  Ok((header, map))
+}
+
+fn boundary_code(boundary: Boundary) -> u8 {
+  match boundary {
+    Boundary::None => 0,
+    Boundary::Convergent => 1,
+    Boundary::Subduction => 2,
+    Boundary::Divergent => 3,
+    Boundary::Transform => 4
+  }
+}
+
+fn boundary_from_code(code: u8) -> Result<Boundary, LoadError> {
+  match code {
+    0 => Ok(Boundary::None),
+    1 => Ok(Boundary::Convergent),
+    2 => Ok(Boundary::Subduction),
+    3 => Ok(Boundary::Divergent),
+    4 => Ok(Boundary::Transform),
+    code => Err(LoadError::InvalidData(format!("unknown boundary {}", code)))
+  }
+}
+
+fn terrain_code(terrain: Terrain) -> u8 {
+  match terrain {
+    Terrain::Trench => 0,
+    Terrain::Ocean => 1,
+    Terrain::Shelf => 2,
+    Terrain::Plains => 3,
+    Terrain::Hill => 4,
+    Terrain::Mountain => 5,
+    Terrain::Badlands => 6
+  }
+}
+
+fn terrain_from_code(code: u8) -> Result<Terrain, LoadError> {
+  match code {
+    0 => Ok(Terrain::Trench),
+    1 => Ok(Terrain::Ocean),
+    2 => Ok(Terrain::Shelf),
+    3 => Ok(Terrain::Plains),
+    4 => Ok(Terrain::Hill),
+    5 => Ok(Terrain::Mountain),
+    6 => Ok(Terrain::Badlands),
+    code => Err(LoadError::InvalidData(format!("unknown terrain {}", code)))
+  }
+}
+
+// Points and vectors stored as flattened coordinate pairs.
+fn pairs(values: Vec<f64>) -> Result<Vec<Point>, LoadError> {
+  let pairs = values.chunks_exact(2);
+  if !pairs.remainder().is_empty() {
+    return Err(LoadError::InvalidData("coordinates must come in pairs".to_string()));
+  }
+  Ok(pairs.map(|pair| Point(pair[0], pair[1])).collect())
+}
+
+// All values are written little-endian.
+#[derive(Default)]
+struct Writer {
+  bytes: Vec<u8>
+}
+
+impl Writer {
+  fn u8(&mut self, value: u8) {
+    self.bytes.push(value);
+  }
+
+  fn u16(&mut self, value: u16) {
+    self.bytes.extend_from_slice(&value.to_le_bytes());
+  }
+
+  fn u32(&mut self, value: u32) {
+    self.bytes.extend_from_slice(&value.to_le_bytes());
+  }
+
+  fn f64(&mut self, value: f64) {
+    self.bytes.extend_from_slice(&value.to_le_bytes());
+  }
+
+  fn string(&mut self, value: &str) {
+    self.u32(value.len() as u32);
+    self.bytes.extend_from_slice(value.as_bytes());
+  }
+
+  fn floats(&mut self, values: &[f64], quantize: bool) {
+    self.u8(if quantize { QUANTIZED_ENCODING } else { RAW_ENCODING });
+    self.u32(values.len() as u32);
+    for value in values.iter() {
+      if quantize {
+        self.bytes.extend_from_slice(&(*value as f32).to_le_bytes());
+      } else {
+        self.f64(*value);
+      }
+    }
+  }
+
+  // Cell indices, `u32::MAX` standing for `None`.
+  fn indices(&mut self, values: &[Option<usize>]) {
+    self.u32(values.len() as u32);
+    for value in values.iter() {
+      self.u32(value.map_or(u32::MAX, |value| value as u32));
+    }
+  }
+
+  fn chunk(&mut self, tag: u8, write: impl FnOnce(&mut Writer)) {
+    let mut chunk = Writer::default();
+    write(&mut chunk);
+    self.u8(tag);
+    self.u32(chunk.bytes.len() as u32);
+    self.bytes.extend_from_slice(&chunk.bytes);
+  }
+}
+
+struct Reader<'a> {
+  bytes: &'a [u8],
+  offset: usize
+}
+
+impl<'a> Reader<'a> {
+  fn take(&mut self, length: usize) -> Result<&'a [u8], LoadError> {
+    let end = self.offset.checked_add(length).ok_or(LoadError::Truncated)?;
+    let bytes = self.bytes.get(self.offset..end).ok_or(LoadError::Truncated)?;
+    self.offset = end;
+    Ok(bytes)
+  }
+
+  fn array<const N: usize>(&mut self) -> Result<[u8; N], LoadError> {
+    let mut array = [0; N];
+    array.copy_from_slice(self.take(N)?);
+    Ok(array)
+  }
+
+  fn u8(&mut self) -> Result<u8, LoadError> {
+    Ok(self.take(1)?[0])
+  }
+
+  fn u16(&mut self) -> Result<u16, LoadError> {
+    Ok(u16::from_le_bytes(self.array()?))
+  }
+
+  fn u32(&mut self) -> Result<u32, LoadError> {
+    Ok(u32::from_le_bytes(self.array()?))
+  }
+
+  fn f64(&mut self) -> Result<f64, LoadError> {
+    Ok(f64::from_le_bytes(self.array()?))
+  }
+
+  fn string(&mut self) -> Result<String, LoadError> {
+    let length = self.u32()? as usize;
+    String::from_utf8(self.take(length)?.to_vec()).map_err(|error| LoadError::InvalidData(error.to_string()))
+  }
+
+  fn floats(&mut self) -> Result<Vec<f64>, LoadError> {
+    let encoding = self.u8()?;
+    let count = self.u32()? as usize;
+    let mut values = Vec::with_capacity(count.min(self.bytes.len()));
+    for _ in 0..count {
+      values.push(match encoding {
+        RAW_ENCODING => self.f64()?,
+        QUANTIZED_ENCODING => f32::from_le_bytes(self.array()?) as f64,
+        encoding => return Err(LoadError::InvalidData(format!("unknown float encoding {}", encoding)))
+      });
+    }
+    Ok(values)
+  }
+
+  fn indices(&mut self) -> Result<Vec<Option<usize>>, LoadError> {
+    let count = self.u32()? as usize;
+    let mut values = Vec::with_capacity(count.min(self.bytes.len()));
+    for _ in 0..count {
+      let value = self.u32()?;
+      values.push(if value == u32::MAX { None } else { Some(value as usize) });
+    }
+    Ok(values)
+  }
+}
@@ -0,0 +1,47 @@
+// Elevation under which a share of `1.0 - land_ratio` cells lie. The level is
+// taken halfway between the highest water cell and the lowest land cell, which
+// end up on either side of sea level once rebased. Cells of equal elevation
+// stay on the same side, so the share is the closest the elevations allow.
+// Undefined elevations are left out.
+pub fn sea_level_for(elevation: &[f64], land_ratio: f64) -> f64 {
+  let mut sorted: Vec<f64> = elevation.iter().cloned().filter(|value| !value.is_nan()).collect();
+  if sorted.is_empty() {
+    return 0.0;
+  }
+  sorted.sort_by(|a, b| a.total_cmp(b));
+
+  let count = sorted.len();
+  let target = land_ratio.clamp(0.0, 1.0) * count as f64;
+  // Land cell counts that don't split cells of equal elevation.
+  let land = (0..=count)
+    .filter(|land| *land == 0 || *land == count || sorted[count - land - 1] < sorted[count - land])
+    .min_by(|a, b| (*a as f64 - target).abs().total_cmp(&(*b as f64 - target).abs()))
+    .unwrap();
+
+  if land == 0 {
+    sorted[count - 1]
+  } else if land == count {
+    sorted[0].next_down()
+  } else {
+    let (water, lowest_land) = (sorted[count - land - 1], sorted[count - land]);
+    // Neighbouring floats have no value in between.
+    let level = water + (lowest_land - water) / 2.0;
+    if level < lowest_land { level } else { water }
+  }
+}
+
+// Shifts elevations so that sea level sits at 0.0 with `land_ratio` of the
+// cells above it.
+pub fn rebase(elevation: &mut [f64], land_ratio: f64) {
+  let sea_level = sea_level_for(elevation, land_ratio);
+  for value in elevation.iter_mut() {
+    *value -= sea_level;
+  }
+}
+
+pub fn land_ratio(elevation: &[f64]) -> f64 {
+  if elevation.is_empty() {
+    return 0.0;
+  }
+  elevation.iter().filter(|value| **value > 0.0).count() as f64 / elevation.len() as f64
+}
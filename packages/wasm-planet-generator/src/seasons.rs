@@ -0,0 +1,215 @@
+use std::f64::consts::PI;
+use crate::climate::ClimateOptions;
+
+pub const MONTHS: usize = 12;
+
+/// Axial tilt of the Earth, in degrees, which `ClimateOptions::seasonal_range`
+/// is given for.
+const EARTH_TILT: f64 = 23.44;
+
+/// Mean monthly temperature from which plants grow, in degrees Celsius.
+const GROWING_TEMPERATURE: f64 = 5.0;
+
+/// Mean monthly temperature under which precipitation falls as snow.
+const SNOW_TEMPERATURE: f64 = 0.0;
+
+/// Latitude spread, in degrees, of the rain belt following the sun, of the dry
+/// subtropical highs on each side of it, and of the mid-latitude storm tracks.
+const RAIN_BELT_SPREAD: f64 = 12.0;
+const HIGHS_SPREAD: f64 = 10.0;
+const STORMS_SPREAD: f64 = 15.0;
+
+/// Latitudes of the subtropical highs and of the storm tracks relative to the
+/// rain belt.
+const HIGHS_OFFSET: f64 = 30.0;
+const STORMS_OFFSET: f64 = 55.0;
+
+// Köppen climate classes, named after their code.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Koppen {
+  Af, Am, Aw, As,
+  BWh, BWk, BSh, BSk,
+  Csa, Csb, Csc, Cwa, Cwb, Cwc, Cfa, Cfb, Cfc,
+  Dsa, Dsb, Dsc, Dsd, Dwa, Dwb, Dwc, Dwd, Dfa, Dfb, Dfc, Dfd,
+  ET, EF
+}
+
+impl Koppen {
+  pub const ALL: [Koppen; 31] = [
+    Koppen::Af, Koppen::Am, Koppen::Aw, Koppen::As,
+    Koppen::BWh, Koppen::BWk, Koppen::BSh, Koppen::BSk,
+    Koppen::Csa, Koppen::Csb, Koppen::Csc, Koppen::Cwa, Koppen::Cwb, Koppen::Cwc, Koppen::Cfa, Koppen::Cfb, Koppen::Cfc,
+    Koppen::Dsa, Koppen::Dsb, Koppen::Dsc, Koppen::Dsd, Koppen::Dwa, Koppen::Dwb, Koppen::Dwc, Koppen::Dwd,
+    Koppen::Dfa, Koppen::Dfb, Koppen::Dfc, Koppen::Dfd,
+    Koppen::ET, Koppen::EF
+  ];
+
+  pub fn code(&self) -> String {
+    format!("{:?}", self)
+  }
+
+  pub fn from_code(code: &str) -> Option<Koppen> {
+    Koppen::ALL.iter().find(|koppen| koppen.code() == code).cloned()
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Seasons {
+  /// Mean temperature of every month, from January, in degrees Celsius.
+  pub temperature: Vec<[f64; MONTHS]>,
+  /// Precipitation of every month, from January, in millimeters.
+  pub precipitation: Vec<[f64; MONTHS]>,
+  /// Months warm enough for plants to grow.
+  pub growing_months: Vec<u8>,
+  /// Months cold enough for precipitation to fall as snow.
+  pub snow_months: Vec<u8>,
+  /// Climate class of land cells, `None` for water.
+  pub koppen: Vec<Option<Koppen>>
+}
+
+// Monthly series of every cell. Temperature swings around the annual mean
+// the more the further from the equator and from water. Precipitation
+// follows a rain belt moving with the sun between the tropics, flanked by dry
+// subtropical highs and, further away, by the storm tracks.
+pub fn simulate(
+  latitudes: &[f64],
+  elevation: &[f64],
+  temperature: &[f64],
+  moisture: &[f64],
+  options: &ClimateOptions
+) -> Seasons {
+  let tilt = options.axial_tilt.to_radians().sin() / EARTH_TILT.to_radians().sin();
+
+  let monthly_temperature = (0..latitudes.len())
+    .map(|cell| {
+      let continentality = 1.0 - moisture[cell];
+      let amplitude = options.seasonal_range * tilt * latitudes[cell].abs() / 90.0 * (0.3 + 0.7 * continentality);
+      let mut months = [0.0; MONTHS];
+      for (month, value) in months.iter_mut().enumerate() {
+        *value = temperature[cell] + amplitude * summer(month) * latitudes[cell].signum();
+      }
+      months
+    })
+    .collect::<Vec<[f64; MONTHS]>>();
+
+  let precipitation = (0..latitudes.len())
+    .map(|cell| {
+      let mut months = [0.0; MONTHS];
+      for (month, value) in months.iter_mut().enumerate() {
+        let rain_belt = options.axial_tilt * summer(month);
+        let offset = latitudes[cell] - rain_belt;
+        let wetness = 0.25 + bell(offset, RAIN_BELT_SPREAD)
+          - 0.4 * bell(offset.abs() - HIGHS_OFFSET, HIGHS_SPREAD)
+          + 0.3 * bell(offset.abs() - STORMS_OFFSET, STORMS_SPREAD);
+        *value = options.max_precipitation * moisture[cell] * wetness.max(0.05);
+      }
+      months
+    })
+    .collect::<Vec<[f64; MONTHS]>>();
+
+  describe(latitudes, elevation, monthly_temperature, precipitation)
+}
+
+// From -1.0 in January to 1.0 in July, the height of the northern summer.
+fn summer(month: usize) -> f64 {
+  -(2.0 * PI * month as f64 / MONTHS as f64).cos()
+}
+
+fn bell(offset: f64, spread: f64) -> f64 {
+  (-(offset / spread).powi(2)).exp()
+}
+
+// Derives the growing season, snow months and climate classes from the
+// monthly series. Shared by generation and loading.
+pub fn describe(
+  latitudes: &[f64],
+  elevation: &[f64],
+  temperature: Vec<[f64; MONTHS]>,
+  precipitation: Vec<[f64; MONTHS]>
+) -> Seasons {
+  let growing_months = temperature
+    .iter()
+    .map(|months| months.iter().filter(|value| **value >= GROWING_TEMPERATURE).count() as u8)
+    .collect();
+  let snow_months = temperature
+    .iter()
+    .zip(precipitation.iter())
+    .map(|(temperatures, amounts)| {
+      temperatures.iter().zip(amounts.iter()).filter(|(value, amount)| **value < SNOW_TEMPERATURE && **amount > 0.0).count() as u8
+    })
+    .collect();
+  let koppen = (0..temperature.len())
+    .map(|cell| {
+      if elevation[cell] <= 0.0 {
+        None
+      } else {
+        Some(classify(&temperature[cell], &precipitation[cell], latitudes[cell] >= 0.0))
+      }
+    })
+    .collect();
+
+  Seasons { temperature, precipitation, growing_months, snow_months, koppen }
+}
+
+// Köppen class of a series, following the usual thresholds. Summer runs from
+// April to September in the northern hemisphere, and from October to March in
+// the southern one.
+fn classify(temperature: &[f64; MONTHS], precipitation: &[f64; MONTHS], northern: bool) -> Koppen {
+  let coldest = temperature.iter().cloned().fold(f64::INFINITY, f64::min);
+  let warmest = temperature.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+  let mean = temperature.iter().sum::<f64>() / MONTHS as f64;
+  let total: f64 = precipitation.iter().sum();
+  let driest = precipitation.iter().cloned().fold(f64::INFINITY, f64::min);
+
+  let is_summer = |month: usize| (3..9).contains(&month) == northern;
+  let summer: Vec<f64> = (0..MONTHS).filter(|month| is_summer(*month)).map(|month| precipitation[month]).collect();
+  let winter: Vec<f64> = (0..MONTHS).filter(|month| !is_summer(*month)).map(|month| precipitation[month]).collect();
+  let min = |values: &[f64]| values.iter().cloned().fold(f64::INFINITY, f64::min);
+  let max = |values: &[f64]| values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+  let summer_total: f64 = summer.iter().sum();
+
+  let code = if warmest < 10.0 {
+    if warmest >= 0.0 { "ET".to_string() } else { "EF".to_string() }
+  } else {
+    let share = if total > 0.0 { summer_total / total } else { 0.5 };
+    let threshold = 20.0 * mean + if share >= 0.7 { 280.0 } else if share >= 0.3 { 140.0 } else { 0.0 };
+    if total < threshold {
+      let kind = if total < threshold / 2.0 { "W" } else { "S" };
+      let heat = if mean >= 18.0 { "h" } else { "k" };
+      format!("B{}{}", kind, heat)
+    } else if coldest >= 18.0 {
+      let dry_summer = (0..MONTHS).filter(|month| is_summer(*month)).any(|month| precipitation[month] == driest);
+      if driest >= 60.0 {
+        "Af".to_string()
+      } else if driest >= 100.0 - total / 25.0 {
+        "Am".to_string()
+      } else if dry_summer {
+        "As".to_string()
+      } else {
+        "Aw".to_string()
+      }
+    } else {
+      let group = if coldest > -3.0 { "C" } else { "D" };
+      let dryness = if min(&summer) < 40.0 && min(&summer) < max(&winter) / 3.0 {
+        "s"
+      } else if min(&winter) < max(&summer) / 10.0 {
+        "w"
+      } else {
+        "f"
+      };
+      let warm_months = temperature.iter().filter(|value| **value >= 10.0).count();
+      let heat = if warmest >= 22.0 {
+        "a"
+      } else if warm_months >= 4 {
+        "b"
+      } else if group == "D" && coldest < -38.0 {
+        "d"
+      } else {
+        "c"
+      };
+      format!("{}{}{}", group, dryness, heat)
+    }
+  };
+
+  Koppen::from_code(&code).unwrap()
+}
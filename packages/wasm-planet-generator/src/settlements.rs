@@ -0,0 +1,180 @@
+extern crate rand;
+
+use rand::{Rng, rngs::StdRng};
+use crate::climate::Climate;
+use crate::distance::distance_field;
+use crate::map::Point;
+use crate::mesh::{Mesh, distance};
+use crate::stage::{Draft, MapStage};
+
+/// Elevation above which land is considered uninhabitable.
+const MAX_ELEVATION: f64 = 0.6;
+
+/// Elevation difference with a neighbour past which a cell isn't flat at all.
+const MAX_SLOPE: f64 = 0.1;
+
+/// Distance to water, in cells, over which its attraction fades out.
+const WATER_RANGE: f64 = 4.0;
+
+/// Most comfortable mean annual temperature, in degrees Celsius, and the
+/// difference from it at which land becomes uninhabitable.
+const COMFORT_TEMPERATURE: f64 = 16.0;
+const TEMPERATURE_TOLERANCE: f64 = 30.0;
+
+/// Share of the score left to chance, so that the best cells don't always win.
+const JITTER: f64 = 0.3;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SettlementOptions {
+  /// Number of settlements placed, capitals included, at most.
+  pub count: usize,
+  /// Minimum distance between two settlements, in map units.
+  pub min_spacing: f64,
+  /// Share of the settlements, besides capitals, that are cities.
+  pub city_ratio: f64,
+  /// Share of the settlements, besides capitals, that are towns.
+  pub town_ratio: f64
+}
+
+impl Default for SettlementOptions {
+  fn default() -> Self {
+    SettlementOptions { count: 40, min_spacing: 30.0, city_ratio: 0.1, town_ratio: 0.3 }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Tier {
+  Capital,
+  City,
+  Town,
+  Village
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Settlement {
+  pub cell: usize,
+  pub position: Point,
+  pub tier: Tier,
+  /// Habitability of the cell, from 0.0 to 1.0.
+  pub score: f64
+}
+
+pub struct SettlementStage(pub SettlementOptions);
+
+impl MapStage for SettlementStage {
+  fn name(&self) -> &str {
+    "settlements"
+  }
+
+  fn key(&self) -> String {
+    serde_json::to_string(&self.0).unwrap()
+  }
+
+  fn run(&self, draft: &mut Draft, random: &mut StdRng) {
+    let rivers = draft.layers.hydrology.as_ref().map(|hydrology| hydrology.rivers.as_slice());
+    let mut scores = habitability(draft.points, draft.elevation, rivers, draft.layers.climate.as_ref(), draft.mesh);
+    if let Some(ice) = &draft.layers.ice {
+      for (score, kind) in scores.iter_mut().zip(ice.cells.iter()) {
+        if kind.is_some() {
+          *score = 0.0;
+        }
+      }
+    }
+    let capitals: Vec<usize> = draft.layers.regions
+      .as_ref()
+      .map(|regions| regions.regions.iter().map(|region| region.capital).collect())
+      .unwrap_or_default();
+    draft.layers.settlements = Some(place(random, draft.points, &scores, &capitals, &self.0));
+  }
+}
+
+// Scores every land cell from 0.0 to 1.0, preferring low and flat land close
+// to the coast or a river, with a mild and wet climate when it is known.
+pub fn habitability(
+  points: &[Point],
+  elevation: &[f64],
+  rivers: Option<&[bool]>,
+  climate: Option<&Climate>,
+  mesh: &Mesh
+) -> Vec<f64> {
+  let sources = (0..points.len())
+    .filter(|cell| elevation[*cell] <= 0.0 || rivers.is_some_and(|rivers| rivers[*cell]));
+  let water = distance_field(points, mesh, sources);
+  let range = WATER_RANGE * mesh.spacing.max(f64::EPSILON);
+
+  (0..points.len())
+    .map(|cell| {
+      if elevation[cell] <= 0.0 || elevation[cell] >= MAX_ELEVATION {
+        return 0.0;
+      }
+
+      let lowland = 1.0 - elevation[cell] / MAX_ELEVATION;
+      let slope = mesh.neighbors(cell)
+        .iter()
+        .map(|neighbor| (elevation[*neighbor].max(0.0) - elevation[cell]).abs())
+        .fold(0.0, f64::max);
+      let flatness = (1.0 - slope / MAX_SLOPE).max(0.0);
+      let proximity = (-water[cell] / range).exp();
+      let comfort = climate.map_or(1.0, |climate| {
+        let temperature = 1.0 - (climate.temperature[cell] - COMFORT_TEMPERATURE).abs() / TEMPERATURE_TOLERANCE;
+        temperature.max(0.0) * (0.5 + 0.5 * climate.moisture[cell])
+      });
+
+      (lowland + flatness + 2.0 * proximity) / 4.0 * comfort
+    })
+    .collect()
+}
+
+// Places the capitals first, then goes down the cells from the most habitable
+// one, skipping those already settled or too close to a settlement. Tiers
+// follow the placement order.
+fn place(
+  random: &mut StdRng,
+  points: &[Point],
+  scores: &[f64],
+  capitals: &[usize],
+  options: &SettlementOptions
+) -> Vec<Settlement> {
+  let mut candidates: Vec<(f64, usize)> = (0..points.len())
+    .filter(|cell| scores[*cell] > 0.0)
+    .map(|cell| (scores[cell] * (1.0 - JITTER + JITTER * random.gen::<f64>()), cell))
+    .collect();
+  candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)));
+
+  let mut cells: Vec<usize> = capitals.iter().take(options.count).cloned().collect();
+  let capital_count = if capitals.is_empty() { 1 } else { cells.len() };
+
+  for (_, cell) in candidates {
+    if cells.len() >= options.count {
+      break;
+    }
+    if !cells.contains(&cell) && cells.iter().all(|other| distance(&points[*other], &points[cell]) >= options.min_spacing) {
+      cells.push(cell);
+    }
+  }
+
+  let others = cells.len().saturating_sub(capital_count) as f64;
+  let cities = capital_count + (others * options.city_ratio).round() as usize;
+  let towns = cities + (others * options.town_ratio).round() as usize;
+
+  cells
+    .into_iter()
+    .enumerate()
+    .map(|(rank, cell)| Settlement {
+      cell,
+      position: points[cell].clone(),
+      tier: if rank < capital_count {
+        Tier::Capital
+      } else if rank < cities {
+        Tier::City
+      } else if rank < towns {
+        Tier::Town
+      } else {
+        Tier::Village
+      },
+      score: scores[cell]
+    })
+    .collect()
+}
@@ -0,0 +1,76 @@
+use crate::map::Point;
+use crate::mesh::Mesh;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct SlopeOptions {
+  /// Height, in map units, of an elevation of 1.0.
+  pub exaggeration: f64
+}
+
+impl Default for SlopeOptions {
+  fn default() -> Self {
+    SlopeOptions { exaggeration: 10.0 }
+  }
+}
+
+// Like the map coordinates, `x` points east and `y` south, the top of the map
+// being north, and `z` points up.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Slope {
+  /// Steepness of every cell, in degrees from the horizontal.
+  pub slope: Vec<f64>,
+  /// Downhill direction of every cell, in degrees clockwise from north, and
+  /// `None` for flat cells.
+  pub aspect: Vec<Option<f64>>,
+  /// Unit normal of the surface at every cell.
+  pub normal: Vec<[f64; 3]>
+}
+
+// Slope, aspect and normal of the surface, sea floor included, with heights
+// scaled by the exaggeration.
+pub fn compute(points: &[Point], elevation: &[f64], mesh: &Mesh, options: &SlopeOptions) -> Slope {
+  let gradients: Vec<Point> = (0..points.len())
+    .map(|cell| {
+      let slope = gradient(points, elevation, mesh, cell);
+      Point(slope.0 * options.exaggeration, slope.1 * options.exaggeration)
+    })
+    .collect();
+
+  let slope = gradients.iter().map(|slope| (slope.0 * slope.0 + slope.1 * slope.1).sqrt().atan().to_degrees()).collect();
+  let aspect = gradients
+    .iter()
+    .map(|slope| {
+      if slope.0 == 0.0 && slope.1 == 0.0 {
+        return None;
+      }
+      // Downhill points east by -dz/dx and north by dz/dy.
+      Some((-slope.0).atan2(slope.1).to_degrees().rem_euclid(360.0))
+    })
+    .collect();
+  let normal = gradients
+    .iter()
+    .map(|slope| {
+      let length = (slope.0 * slope.0 + slope.1 * slope.1 + 1.0).sqrt();
+      [-slope.0 / length, -slope.1 / length, 1.0 / length]
+    })
+    .collect();
+
+  Slope { slope, aspect, normal }
+}
+
+// Gradient of a per-cell value, from central differences along the columns
+// and rows of the lattice, or one-sided ones on its edges.
+pub fn gradient(points: &[Point], values: &[f64], mesh: &Mesh, cell: usize) -> Point {
+  let (col, row) = mesh.coords(cell);
+  let difference = |before: usize, after: usize, axis: fn(&Point) -> f64| {
+    let run = axis(&points[after]) - axis(&points[before]);
+    if run.abs() > f64::EPSILON { (values[after] - values[before]) / run } else { 0.0 }
+  };
+
+  let left = mesh.index(col.saturating_sub(1), row);
+  let right = mesh.index((col + 1).min(mesh.cols - 1), row);
+  let up = mesh.index(col, row.saturating_sub(1));
+  let down = mesh.index(col, (row + 1).min(mesh.rows - 1));
+  Point(difference(left, right, |point| point.0), difference(up, down, |point| point.1))
+}
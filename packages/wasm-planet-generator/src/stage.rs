@@ -0,0 +1,93 @@
+extern crate rand;
+
+use rand::rngs::StdRng;
+use std::collections::BTreeMap;
+use crate::circulation::Circulation;
+use crate::climate::Climate;
+use crate::elevation::Terrain;
+use crate::hydrology::Hydrology;
+use crate::ice::Ice;
+use crate::map::Point;
+use crate::mesh::Mesh;
+use crate::regions::Regions;
+use crate::resources::Deposit;
+use crate::roads::Road;
+use crate::settlements::Settlement;
+use crate::tectonics::Tectonics;
+
+// Pass run over the map once its elevation is final, in the order the stages
+// were registered, after the built-in stages (hydrology, circulation, climate,
+// ice, resources, regions, settlements and roads).
+pub trait MapStage {
+  // Unique name of the stage, which also seeds its random number generator.
+  fn name(&self) -> &str;
+
+  // Describes the parameters of the stage, for the pipeline to tell when its
+  // cached output is stale.
+  fn key(&self) -> String {
+    String::new()
+  }
+
+  fn run(&self, draft: &mut Draft, random: &mut StdRng);
+}
+
+// Layers added by the stages, carried over to the map.
+#[derive(Clone, Default)]
+pub struct Layers {
+  pub hydrology: Option<Hydrology>,
+  pub circulation: Option<Circulation>,
+  pub climate: Option<Climate>,
+  pub ice: Option<Ice>,
+  pub resources: Option<Vec<Vec<Deposit>>>,
+  pub regions: Option<Regions>,
+  pub settlements: Option<Vec<Settlement>>,
+  pub roads: Option<Vec<Road>>,
+  /// Per-cell layers added by custom stages, by name.
+  pub named: BTreeMap<String, Vec<f64>>
+}
+
+// Map in progress, as seen by the stages.
+pub struct Draft<'a> {
+  pub points: &'a [Point],
+  pub mesh: &'a Mesh,
+  pub elevation: &'a [f64],
+  /// Terrain of every cell, when it is classified.
+  pub terrain: Option<&'a [Terrain]>,
+  /// Plates and boundaries, when tectonics are simulated.
+  pub tectonics: Option<&'a Tectonics>,
+  /// Added to every temperature, see `Options::temperature_offset`.
+  pub temperature_offset: f64,
+  pub layers: Layers
+}
+
+impl<'a> Draft<'a> {
+  pub fn len(&self) -> usize {
+    self.points.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.points.is_empty()
+  }
+
+  // Indices of the cells sharing an edge with `cell`.
+  pub fn neighbors(&self, cell: usize) -> Vec<usize> {
+    self.mesh.neighbors(cell)
+  }
+
+  pub fn layer(&self, name: &str) -> Option<&[f64]> {
+    self.layers.named.get(name).map(|values| values.as_slice())
+  }
+
+  // Adds a layer holding one value per cell, replacing any layer of the same
+  // name.
+  pub fn add_layer(&mut self, name: &str, values: Vec<f64>) {
+    assert_eq!(values.len(), self.len(), "layer {} must have one value per cell", name);
+    self.layers.named.insert(name.to_string(), values);
+  }
+}
+
+// Seed of the random number generator handed to a stage, derived from its name
+// so that stages don't depend on the ones registered before them.
+pub fn stage_seed(seed: u64, name: &str) -> u64 {
+  name.bytes().fold(seed ^ 0xcbf2_9ce4_8422_2325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3))
+}
@@ -0,0 +1,256 @@
+extern crate rand;
+
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::index::sample};
+use std::collections::VecDeque;
+use crate::map::Point;
+use crate::mesh::{Mesh, distance};
+
+/// Base elevation of continental plates, in planetary elevation units.
+const CONTINENTAL_BASE: f64 = 0.1;
+
+/// Base elevation of oceanic plates, in planetary elevation units.
+const OCEANIC_BASE: f64 = -0.5;
+
+/// Relative plate speed under which a boundary is considered a transform
+/// fault (plates sliding past each other) rather than converging or diverging.
+const TRANSFORM_THRESHOLD: f64 = 0.1;
+
+/// Uplift caused by two continental plates colliding.
+const COLLISION_UPLIFT: f64 = 0.6;
+
+/// Uplift of the continental side of a subduction zone (volcanic range).
+const VOLCANIC_RANGE_UPLIFT: f64 = 0.4;
+
+/// Uplift of an island arc, where an oceanic plate dives under another one.
+const ISLAND_ARC_UPLIFT: f64 = 0.55;
+
+/// Depth of the trench on the oceanic side of a subduction zone.
+const TRENCH_DEPTH: f64 = -0.3;
+
+/// Depth of a rift valley where continental plates pull apart.
+const RIFT_DEPTH: f64 = -0.2;
+
+/// Uplift of a mid-ocean ridge where oceanic plates pull apart.
+const RIDGE_UPLIFT: f64 = 0.15;
+
+#[derive(Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct TectonicsOptions {
+  /// Number of plates seeded over the mesh.
+  pub plates: usize,
+  /// Probability for a plate to be oceanic rather than continental.
+  pub oceanic_ratio: f64,
+  /// How much the plate base elevation replaces the noise base elevation,
+  /// from 0.0 (noise only) to 1.0 (plates only).
+  pub blend: f64,
+  /// Scaling applied to the uplift derived at plate boundaries.
+  pub uplift: f64,
+  /// Distance, in map units, over which boundary uplift fades out.
+  pub boundary_width: f64
+}
+
+impl Default for TectonicsOptions {
+  fn default() -> Self {
+    TectonicsOptions {
+      plates: 12,
+      oceanic_ratio: 0.6,
+      blend: 0.5,
+      uplift: 1.0,
+      boundary_width: 40.0
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PlateKind {
+  Oceanic,
+  Continental
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Boundary {
+  None,
+  Convergent,
+  Subduction,
+  Divergent,
+  Transform
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Plate {
+  pub kind: PlateKind,
+  pub origin: usize,
+  pub velocity: Point
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Tectonics {
+  pub plates: Vec<Plate>,
+  pub plate_ids: Vec<usize>,
+  pub boundaries: Vec<Boundary>,
+  pub uplift: Vec<f64>
+}
+
+pub fn simulate(seed: u64, points: &[Point], mesh: &Mesh, options: &TectonicsOptions) -> Tectonics {
+  let mut random: StdRng = SeedableRng::seed_from_u64(seed.wrapping_add(1));
+
+  let plates = seed_plates(&mut random, points.len(), options);
+  let plate_ids = grow_plates(&mut random, &plates, mesh);
+  let (boundaries, sources) = find_boundaries(points, mesh, &plates, &plate_ids);
+  let uplift = spread_uplift(points, mesh, &sources, options.boundary_width);
+
+  Tectonics { plates, plate_ids, boundaries, uplift }
+}
+
+// Replaces part of the noise base elevation by the plate base elevation, then
+// stamps the boundary uplift on top of it.
+pub fn deform(elevation: &mut [f64], tectonics: &Tectonics, options: &TectonicsOptions) {
+  for (i, value) in elevation.iter_mut().enumerate() {
+    let base = match tectonics.plates[tectonics.plate_ids[i]].kind {
+      PlateKind::Continental => CONTINENTAL_BASE,
+      PlateKind::Oceanic => OCEANIC_BASE
+    };
+    *value = *value * (1.0 - options.blend) + base * options.blend + tectonics.uplift[i] * options.uplift;
+  }
+}
+
+fn seed_plates(random: &mut StdRng, count: usize, options: &TectonicsOptions) -> Vec<Plate> {
+  if count == 0 {
+    return Vec::new();
+  }
+
+  sample(random, count, options.plates.clamp(1, count))
+    .into_vec()
+    .into_iter()
+    .map(|origin| {
+      let angle = random.gen::<f64>() * std::f64::consts::PI * 2.0;
+      let speed = random.gen::<f64>();
+      let kind = if random.gen::<f64>() < options.oceanic_ratio { PlateKind::Oceanic } else { PlateKind::Continental };
+      Plate { kind, origin, velocity: Point(angle.cos() * speed, angle.sin() * speed) }
+    })
+    .collect()
+}
+
+// Grows every plate from its origin at once, expanding a random frontier cell
+// at each step so that plates get irregular outlines.
+fn grow_plates(random: &mut StdRng, plates: &[Plate], mesh: &Mesh) -> Vec<usize> {
+  let mut plate_ids = vec![usize::MAX; mesh.len()];
+  let mut frontier = Vec::new();
+
+  for (id, plate) in plates.iter().enumerate() {
+    plate_ids[plate.origin] = id;
+    frontier.push(plate.origin);
+  }
+
+  while !frontier.is_empty() {
+    let cell = frontier.swap_remove(random.gen_range(0, frontier.len()));
+    for neighbor in mesh.neighbors(cell) {
+      if plate_ids[neighbor] == usize::MAX {
+        plate_ids[neighbor] = plate_ids[cell];
+        frontier.push(neighbor);
+      }
+    }
+  }
+
+  plate_ids
+}
+
+// Classifies every cell touching another plate from the strongest relative
+// motion across its edges, and returns the uplift each boundary cell emits.
+fn find_boundaries(
+  points: &[Point],
+  mesh: &Mesh,
+  plates: &[Plate],
+  plate_ids: &[usize]
+) -> (Vec<Boundary>, Vec<(usize, f64)>) {
+  let mut boundaries = vec![Boundary::None; points.len()];
+  let mut sources = Vec::new();
+
+  for cell in 0..points.len() {
+    let plate = &plates[plate_ids[cell]];
+    let mut strongest: Option<(f64, &Plate)> = None;
+
+    for neighbor in mesh.neighbors(cell) {
+      if plate_ids[neighbor] == plate_ids[cell] {
+        continue;
+      }
+      let other = &plates[plate_ids[neighbor]];
+      let length = distance(&points[cell], &points[neighbor]).max(f64::EPSILON);
+      let normal = Point(
+        (points[neighbor].0 - points[cell].0) / length,
+        (points[neighbor].1 - points[cell].1) / length
+      );
+      let convergence = (plate.velocity.0 - other.velocity.0) * normal.0
+        + (plate.velocity.1 - other.velocity.1) * normal.1;
+
+      if strongest.is_none_or(|(value, _)| convergence.abs() > value.abs()) {
+        strongest = Some((convergence, other));
+      }
+    }
+
+    if let Some((convergence, other)) = strongest {
+      let strength = 0.5 + 0.5 * convergence.abs().min(1.0);
+      let (boundary, uplift) = classify(plate.kind, other.kind, convergence);
+      boundaries[cell] = boundary;
+      if uplift != 0.0 {
+        sources.push((cell, uplift * strength));
+      }
+    }
+  }
+
+  (boundaries, sources)
+}
+
+fn classify(kind: PlateKind, other: PlateKind, convergence: f64) -> (Boundary, f64) {
+  use PlateKind::*;
+
+  if convergence > TRANSFORM_THRESHOLD {
+    match (kind, other) {
+      (Continental, Continental) => (Boundary::Convergent, COLLISION_UPLIFT),
+      (Continental, Oceanic) => (Boundary::Subduction, VOLCANIC_RANGE_UPLIFT),
+      (Oceanic, Continental) => (Boundary::Subduction, TRENCH_DEPTH),
+      (Oceanic, Oceanic) => (Boundary::Subduction, ISLAND_ARC_UPLIFT)
+    }
+  } else if convergence < -TRANSFORM_THRESHOLD {
+    match kind {
+      Continental => (Boundary::Divergent, RIFT_DEPTH),
+      Oceanic => (Boundary::Divergent, RIDGE_UPLIFT)
+    }
+  } else {
+    (Boundary::Transform, 0.0)
+  }
+}
+
+// Spreads the uplift of boundary cells to their surroundings, fading it out
+// with the distance to the closest boundary cell.
+fn spread_uplift(points: &[Point], mesh: &Mesh, sources: &[(usize, f64)], width: f64) -> Vec<f64> {
+  let mut uplift = vec![0.0; points.len()];
+  let mut origin = vec![usize::MAX; points.len()];
+  let mut queue = VecDeque::new();
+  let width = width.max(f64::EPSILON);
+
+  for (source, (cell, _)) in sources.iter().enumerate() {
+    origin[*cell] = source;
+    queue.push_back(*cell);
+  }
+
+  while let Some(cell) = queue.pop_front() {
+    let (source_cell, amount) = sources[origin[cell]];
+    let ratio = 1.0 - distance(&points[cell], &points[source_cell]) / width;
+    if ratio <= 0.0 {
+      continue;
+    }
+    uplift[cell] = amount * ratio * ratio;
+
+    for neighbor in mesh.neighbors(cell) {
+      if origin[neighbor] == usize::MAX {
+        origin[neighbor] = origin[cell];
+        queue.push_back(neighbor);
+      }
+    }
+  }
+
+  uplift
+}
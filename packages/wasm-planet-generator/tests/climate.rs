@@ -0,0 +1,122 @@
+//! Winds, climate, seasons and ice.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::circulation::CirculationOptions;
+use wasm_planet_generator::climate::ClimateOptions;
+use wasm_planet_generator::ice::{IceKind, IceOptions};
+use wasm_planet_generator::map::{Map, Point};
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::seasons::Koppen;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn winds_follow_latitude_bands() {
+  let options = Options { circulation: Some(CirculationOptions::default()), ..Options::default() };
+  let map = earth(&options);
+  let mesh = map.mesh();
+  let circulation = map.circulation.as_ref().unwrap();
+  let length = |vector: &Point| (vector.0 * vector.0 + vector.1 * vector.1).sqrt();
+
+  for cell in 0..map.points.len() {
+    assert!(length(&circulation.wind[cell]) <= 1.0 + 1e-9);
+    assert!(length(&circulation.currents[cell]) <= 1.0 + 1e-9);
+    if map.elevation[cell] > 0.0 {
+      assert_eq!(length(&circulation.currents[cell]), 0.0);
+      continue;
+    }
+    // Over open water, winds aren't deflected.
+    if mesh.neighbors(cell).iter().any(|neighbor| map.elevation[*neighbor] > 0.0) {
+      continue;
+    }
+    let latitude = (0.5 - map.points[cell].1 / 300.0).abs() * 180.0;
+    let eastward = circulation.wind[cell].0;
+    match latitude {
+      latitude if (5.0..25.0).contains(&latitude) => assert!(eastward < 0.0),
+      latitude if (35.0..55.0).contains(&latitude) => assert!(eastward > 0.0),
+      latitude if (65.0..85.0).contains(&latitude) => assert!(eastward < 0.0),
+      _ => {}
+    }
+  }
+  assert!(circulation.currents.iter().any(|current| length(current) > 0.5));
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn seasons_swing_around_annual_climate() {
+  let climate_options = ClimateOptions { seasons: true, ..ClimateOptions::default() };
+  let options = Options { climate: Some(climate_options.clone()), ..Options::default() };
+  let map = earth(&options);
+  let climate = map.climate.unwrap();
+  let seasons = climate.seasons.unwrap();
+
+  for cell in 0..map.points.len() {
+    let months = &seasons.temperature[cell];
+    assert!((months.iter().sum::<f64>() / 12.0 - climate.temperature[cell]).abs() < 1e-9);
+    let latitude = 90.0 - map.points[cell].1 / 300.0 * 180.0;
+    if latitude > 10.0 {
+      assert!(months[6] > months[0]);
+    } else if latitude < -10.0 {
+      assert!(months[6] < months[0]);
+    }
+    assert!(seasons.precipitation[cell].iter().all(|amount| *amount >= 0.0));
+    assert!(seasons.growing_months[cell] <= 12 && seasons.snow_months[cell] <= 12);
+
+    let warmest = months.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    match seasons.koppen[cell] {
+      None => assert!(map.elevation[cell] <= 0.0),
+      Some(Koppen::ET) | Some(Koppen::EF) => assert!(warmest < 10.0),
+      Some(_) => assert!(warmest >= 10.0)
+    }
+  }
+  let mut classes: Vec<String> = seasons.koppen.iter().flatten().map(|koppen| koppen.code()).collect();
+  classes.sort();
+  classes.dedup();
+  assert!(classes.len() >= 5);
+
+  // Without axial tilt, there are no seasons to speak of.
+  let options = Options { climate: Some(ClimateOptions { axial_tilt: 0.0, ..climate_options }), ..Options::default() };
+  let map = earth(&options);
+  let seasons = map.climate.unwrap().seasons.unwrap();
+  assert!(seasons.temperature.iter().all(|months| months.iter().all(|value| (value - months[0]).abs() < 1e-9)));
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn ice_spreads_as_the_planet_cools() {
+  let iced = |temperature_offset: f64, climate: Option<ClimateOptions>| {
+    let options = Options { temperature_offset, climate, ice: Some(IceOptions::default()), ..Options::default() };
+    earth(&options)
+  };
+  let frozen = |map: &Map| map.ice.as_ref().unwrap().cells.iter().filter(|kind| kind.is_some()).count();
+
+  let map = iced(0.0, Some(ClimateOptions::default()));
+  let ice = map.ice.as_ref().unwrap();
+  for (cell, kind) in ice.cells.iter().enumerate() {
+    match kind {
+      Some(IceKind::Sea) => assert!(map.elevation[cell] <= 0.0),
+      Some(_) => assert!(map.elevation[cell] > 0.0),
+      None => {}
+    }
+  }
+  assert!(!ice.glaciers.is_empty());
+  for glacier in ice.glaciers.iter() {
+    assert_eq!(ice.cells[glacier[0]], Some(IceKind::Glacier));
+    assert!(glacier.windows(2).all(|pair| map.elevation[pair[1]] < map.elevation[pair[0]]));
+  }
+
+  assert!(frozen(&iced(-6.0, Some(ClimateOptions::default()))) > frozen(&map));
+  assert!(frozen(&iced(6.0, Some(ClimateOptions::default()))) < frozen(&map));
+
+  // Without a climate, ice follows the default one, offset all the same.
+  let fallback = iced(0.0, None);
+  assert_eq!(fallback.ice.as_ref().unwrap().cells, ice.cells);
+  assert!(frozen(&iced(-6.0, None)) > frozen(&fallback));
+  assert!(frozen(&iced(6.0, None)) < frozen(&fallback));
+}
@@ -0,0 +1,9 @@
+//! Fixtures shared by the feature tests.
+
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::options::Options;
+
+// A map large enough for the features tests to find a bit of everything.
+pub fn earth(options: &Options) -> Map {
+  Map::generate_with("earth".to_string(), 400.0, 300.0, 8.0, 0.5, options)
+}
@@ -0,0 +1,49 @@
+//! Distance fields over the cells.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::map::Point;
+use wasm_planet_generator::mesh::distance;
+use wasm_planet_generator::options::Options;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn distance_fields_follow_the_cell_graph() {
+  let options = Options { hydrology: Some(HydrologyOptions::default()), distances: true, ..Options::default() };
+  let map = earth(&options);
+  let mesh = map.mesh();
+  let distances = map.distances.as_ref().unwrap();
+  let rivers = &map.hydrology.as_ref().unwrap().rivers;
+  let land = |cell: usize| map.elevation[cell] > 0.0;
+  // Every cell is either a source at its starting distance, or one step further
+  // than its closest neighbour.
+  let settled = |field: &[f64], start: &dyn Fn(usize) -> Option<f64>| {
+    for cell in 0..field.len() {
+      let through = mesh.neighbors(cell)
+        .iter()
+        .map(|neighbor| field[*neighbor] + distance(&map.points[cell], &map.points[*neighbor]))
+        .fold(f64::INFINITY, f64::min);
+      let expected = start(cell).map_or(through, |start| start.min(through));
+      assert!((field[cell] - expected).abs() < 1e-9, "{} instead of {} at {}", field[cell], expected, cell);
+    }
+  };
+
+  let to_water: Vec<f64> = (0..map.points.len()).map(|cell| if land(cell) { distances.coast[cell] } else { 0.0 }).collect();
+  let to_land: Vec<f64> = (0..map.points.len()).map(|cell| if land(cell) { 0.0 } else { -distances.coast[cell] }).collect();
+  assert!((0..map.points.len()).all(|cell| (distances.coast[cell] > 0.0) == land(cell)));
+  settled(&to_water, &|cell| if land(cell) { None } else { Some(0.0) });
+  settled(&to_land, &|cell| if land(cell) { Some(0.0) } else { None });
+  settled(distances.river.as_ref().unwrap(), &|cell| if rivers[cell] { Some(0.0) } else { None });
+
+  let straight = |point: &Point| point.0.min(point.1).min(400.0 - point.0).min(300.0 - point.1).max(0.0);
+  settled(&distances.edge, &|cell| if mesh.is_border(cell) { Some(straight(&map.points[cell])) } else { None });
+  assert!((0..map.points.len()).all(|cell| distances.edge[cell] >= straight(&map.points[cell]) - 1e-9));
+  assert!(earth(&Options::default()).distances.is_none());
+}
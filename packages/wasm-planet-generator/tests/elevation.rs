@@ -0,0 +1,138 @@
+//! Elevation, sea level, terrain and slopes.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::elevation::{NoiseLayer, Terrain, elevate_with_layers};
+use wasm_planet_generator::map::{Map, Point};
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::points::generate_points;
+use wasm_planet_generator::sea_level;
+use wasm_planet_generator::slope::SlopeOptions;
+use wasm_planet_generator::tectonics::TectonicsOptions;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+// Elevating the whole map at once, split across threads with the `parallel`
+// feature, gives the same values as elevating it a slice at a time, each slice
+// small enough to be elevated on a single thread.
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn elevation_does_not_depend_on_threading() {
+  let points = generate_points(400.0, 300.0, 4.0);
+  let layers = [NoiseLayer::ContinentDef];
+  let elevate = |points: &[Point]| elevate_with_layers(7, points, 400.0, 300.0, &layers, true, &mut |_| true).unwrap();
+
+  let whole = elevate(&points);
+  let mut sliced = Vec::new();
+  let mut terrain = Vec::new();
+  let mut layer = Vec::new();
+  for slice in points.chunks(1000) {
+    let elevated = elevate(slice);
+    sliced.extend(elevated.elevation);
+    terrain.extend(elevated.terrain);
+    layer.extend(elevated.layers[0].iter().cloned());
+  }
+  assert_eq!(whole.elevation, sliced);
+  assert_eq!(whole.terrain, terrain);
+  assert_eq!(whole.layers[0], layer);
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn sea_level_reaches_the_target_land_ratio() {
+  for target in [0.0, 0.3, 0.5, 1.0].iter() {
+    let options = Options { target_land_ratio: Some(*target), ..Options::default() };
+    let map = Map::generate_with("earth".to_string(), 160.0, 120.0, 8.0, 0.5, &options);
+    let count = map.elevation.len() as f64;
+    assert!((map.land_ratio - target).abs() <= 0.5 / count, "{} land for {}", map.land_ratio, target);
+  }
+
+  // Far from 0.0, where a single epsilon is lost to rounding.
+  let mut elevation = vec![5.0, 6.0, 7.0, 8.0];
+  sea_level::rebase(&mut elevation, 1.0);
+  assert_eq!(sea_level::land_ratio(&elevation), 1.0);
+  let mut elevation = vec![5.0, 6.0, 7.0, 8.0];
+  sea_level::rebase(&mut elevation, 0.0);
+  assert_eq!(sea_level::land_ratio(&elevation), 0.0);
+
+  // Cells of equal elevation stay on the same side, none at sea level.
+  let mut elevation = vec![1.0, 2.0, 2.0, 2.0, 3.0];
+  sea_level::rebase(&mut elevation, 0.6);
+  assert_eq!(sea_level::land_ratio(&elevation), 0.8);
+  assert!(elevation.iter().all(|value| *value != 0.0));
+
+  let mut elevation = vec![1.0, f64::NAN, 3.0, 4.0];
+  sea_level::rebase(&mut elevation, 0.25);
+  assert!(elevation[3] > 0.0 && elevation[0] <= 0.0);
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn noise_layers_come_along_elevation() {
+  let options = Options { noise_layers: vec![NoiseLayer::ContinentDef, NoiseLayer::ContinentsWithRivers], ..Options::default() };
+  let map = Map::generate_with("earth".to_string(), 100.0, 80.0, 10.0, 0.5, &options);
+  let plain = Map::generate("earth".to_string(), 100.0, 80.0, 10.0, 0.5);
+
+  assert_eq!(map.elevation, plain.elevation);
+  assert_eq!(map.noise_layers["continentDef"].len(), map.points.len());
+  assert_eq!(map.noise_layers["continentsWithRivers"], map.elevation);
+  assert!(plain.noise_layers.is_empty());
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn terrain_follows_elevation() {
+  let classified = |options: Options| {
+    let map = earth(&Options { terrain: true, ..options });
+    let terrain = map.terrain.as_ref().unwrap();
+    assert_eq!(terrain.len(), map.points.len());
+    for (kind, elevation) in terrain.iter().zip(map.elevation.iter()) {
+      assert_eq!(*kind >= Terrain::Plains, *elevation > 0.0, "{:?} at {}", kind, elevation);
+    }
+    assert!(terrain.contains(&Terrain::Mountain));
+    map
+  };
+
+  classified(Options::default());
+  classified(Options { target_land_ratio: Some(0.6), ..Options::default() });
+  // Mountains rise along converging plates.
+  let map = classified(Options { tectonics: Some(TectonicsOptions::default()), ..Options::default() });
+  let tectonics = map.tectonics.as_ref().unwrap();
+  let terrain = map.terrain.as_ref().unwrap();
+  assert!((0..terrain.len()).any(|cell| terrain[cell] == Terrain::Mountain && tectonics.uplift[cell] >= 0.25));
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn slopes_steepen_with_exaggeration() {
+  let sloped = |exaggeration: f64| {
+    let options = Options { slope: Some(SlopeOptions { exaggeration }), ..Options::default() };
+    earth(&options).slope.unwrap()
+  };
+
+  let slope = sloped(10.0);
+  for cell in 0..slope.slope.len() {
+    let [x, y, z] = slope.normal[cell];
+    assert!(((x * x + y * y + z * z).sqrt() - 1.0).abs() < 1e-9);
+    assert!((z - slope.slope[cell].to_radians().cos()).abs() < 1e-9);
+    // The normal leans downhill.
+    if let Some(aspect) = slope.aspect[cell] {
+      assert!((0.0..360.0).contains(&aspect));
+      let bearing = x.atan2(-y).to_degrees().rem_euclid(360.0);
+      let difference = (bearing - aspect).abs();
+      assert!(difference.min(360.0 - difference) < 1e-6);
+    }
+  }
+  assert!(slope.slope.iter().any(|value| *value > 1.0));
+
+  let steeper = sloped(20.0);
+  for cell in 0..slope.slope.len() {
+    let ratio = steeper.slope[cell].to_radians().tan() / slope.slope[cell].to_radians().tan().max(f64::EPSILON);
+    assert!(slope.slope[cell] == 0.0 || (ratio - 2.0).abs() < 1e-6);
+  }
+  assert!(sloped(0.0).slope.iter().all(|value| *value == 0.0));
+}
@@ -0,0 +1,37 @@
+//! Progress, cancellation and metadata of a generation.
+
+extern crate wasm_bindgen_test;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::progress::{Cancelled, Stage};
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn cancelling_stops_generation() {
+  let mut stages = Vec::new();
+  let result = Map::generate_with_progress("cancel".to_string(), 100.0, 100.0, 10.0, 0.5, &Options::default(), &mut |stage, _| {
+    stages.push(stage);
+    stage != Stage::Elevation
+  });
+
+  assert_eq!(result.err(), Some(Cancelled));
+  assert_eq!(stages.last(), Some(&Stage::Elevation));
+  assert!(!stages.contains(&Stage::PostProcessing));
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn maps_describe_their_generation() {
+  let map = Map::generate("earth".to_string(), 100.0, 80.0, 10.0, 0.5);
+  let stages: Vec<Stage> = map.meta.timings.iter().map(|timing| timing.stage).collect();
+
+  assert_eq!(map.meta.seed, "earth");
+  assert_eq!((map.meta.width, map.meta.height, map.meta.spacing, map.meta.chaos), (100.0, 80.0, 10.0, 0.5));
+  assert_eq!(map.meta.generator_version, env!("CARGO_PKG_VERSION"));
+  assert_eq!(map.meta.elevation.sea_level, wasm_planet_generator::elevation::SEA_LEVEL);
+  assert_eq!(stages, vec![Stage::Points, Stage::Perturbation, Stage::Elevation, Stage::PostProcessing]);
+}
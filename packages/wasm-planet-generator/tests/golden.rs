@@ -0,0 +1,115 @@
+//! Golden-output regression suite, run natively with `cargo test` and in
+//! headless browsers with `wasm-pack test`.
+//!
+//! Each case generates a map and compares hashes of its quantized points and
+//! elevations against `tests/golden/maps.txt`. After an intended change to the
+//! generation, regenerate the golden file natively with
+//! `UPDATE_GOLDEN=1 cargo test --test golden`.
+
+extern crate wasm_bindgen_test;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::tectonics::TectonicsOptions;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+const GOLDEN: &str = include_str!("golden/maps.txt");
+
+/// Values are rounded to this precision before hashing, so that tiny floating
+/// point differences between targets don't break the snapshots.
+const QUANTUM: f64 = 1e-6;
+
+struct Case {
+  name: &'static str,
+  seed: &'static str,
+  width: f64,
+  height: f64,
+  spacing: f64,
+  chaos: f64,
+  options: fn() -> Options
+}
+
+fn cases() -> Vec<Case> {
+  vec![
+    Case { name: "earth", seed: "earth", width: 160.0, height: 120.0, spacing: 8.0, chaos: 0.5, options: Options::default },
+    Case { name: "mars", seed: "mars", width: 200.0, height: 100.0, spacing: 5.0, chaos: 1.0, options: Options::default },
+    Case { name: "empty-seed", seed: "", width: 100.0, height: 100.0, spacing: 10.0, chaos: 0.0, options: Options::default },
+    Case { name: "long-seed", seed: "a planet seed that is long enough to overflow", width: 120.0, height: 80.0, spacing: 6.0, chaos: 0.3, options: Options::default },
+    Case {
+      name: "tectonics",
+      seed: "pangea",
+      width: 160.0,
+      height: 120.0,
+      spacing: 8.0,
+      chaos: 0.7,
+      options: || Options {
+        tectonics: Some(TectonicsOptions::default()),
+        target_land_ratio: Some(0.3),
+        ..Options::default()
+      }
+    }
+  ]
+}
+
+// FNV-1a over quantized values.
+fn hash(values: impl Iterator<Item = f64>) -> u64 {
+  let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+  for value in values {
+    for byte in ((value / QUANTUM).round() as i64).to_le_bytes().iter() {
+      hash ^= *byte as u64;
+      hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+  }
+  hash
+}
+
+fn snapshot(case: &Case) -> String {
+  let map = Map::generate_with(case.seed.to_string(), case.width, case.height, case.spacing, case.chaos, &(case.options)());
+  let points = hash(map.points.iter().flat_map(|point| vec![point.0, point.1]));
+  let elevation = hash(map.elevation.iter().cloned());
+  format!("{} {} {:016x} {:016x}", case.name, map.points.len(), points, elevation)
+}
+
+fn snapshots() -> Vec<String> {
+  cases().iter().map(snapshot).collect()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn update_golden(snapshots: &[String]) -> bool {
+  if std::env::var("UPDATE_GOLDEN").is_err() {
+    return false;
+  }
+  let path = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/maps.txt");
+  std::fs::write(path, snapshots.join("\n") + "\n").unwrap();
+  true
+}
+
+#[cfg(target_arch = "wasm32")]
+fn update_golden(_snapshots: &[String]) -> bool {
+  false
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn maps_match_golden_snapshots() {
+  let snapshots = snapshots();
+  if update_golden(&snapshots) {
+    return;
+  }
+
+  let golden: Vec<&str> = GOLDEN.lines().collect();
+  assert_eq!(golden.len(), snapshots.len(), "golden file and cases are out of sync");
+  for (expected, actual) in golden.iter().zip(snapshots.iter()) {
+    assert_eq!(expected, actual);
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn generation_is_deterministic() {
+  for case in cases().iter().take(2) {
+    assert_eq!(snapshot(case), snapshot(case));
+  }
+}
@@ -0,0 +1,64 @@
+//! Drainage and rivers.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::hydrology::{self, HydrologyOptions};
+use wasm_planet_generator::mesh::Mesh;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::points::generate_points;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn rivers_drain_down_to_the_sea() {
+  // A slope down to a sea along the first row, with a pit in the middle.
+  let mesh = Mesh::new(&generate_points(100.0, 100.0, 10.0), 100.0, 100.0, 10.0);
+  let mut elevation: Vec<f64> = (0..mesh.len()).map(|cell| mesh.coords(cell).1 as f64 * 0.1 - 0.05).collect();
+  let pit = mesh.index(5, 5);
+  elevation[pit] = 0.01;
+  let hydrology = hydrology::drain(&elevation, &mesh, &HydrologyOptions::default());
+  for cell in 0..mesh.len() {
+    match hydrology.downstream[cell] {
+      Some(next) if cell != pit => assert!(elevation[next] < elevation[cell]),
+      Some(next) => assert!(mesh.neighbors(pit).contains(&next)),
+      None => assert!(elevation[cell] <= 0.0 || mesh.is_border(cell))
+    }
+  }
+
+  let options = Options { hydrology: Some(HydrologyOptions::default()), ..Options::default() };
+  let map = earth(&options);
+  let mesh = map.mesh();
+  let hydrology = map.hydrology.as_ref().unwrap();
+  let mut upstream = vec![0.0; map.points.len()];
+  for cell in 0..map.points.len() {
+    let land = map.elevation[cell] > 0.0;
+    assert_eq!(hydrology.downstream[cell].is_some(), land && !mesh.is_border(cell));
+    assert_eq!(hydrology.rivers[cell], land && hydrology.flux[cell] >= 0.002 * map.points.len() as f64);
+    if let Some(next) = hydrology.downstream[cell] {
+      assert!(mesh.neighbors(cell).contains(&next));
+      upstream[next] += hydrology.flux[cell];
+      // Rivers only grow on their way down.
+      assert!(!hydrology.rivers[cell] || hydrology.rivers[next] || map.elevation[next] <= 0.0 || mesh.is_border(next));
+    }
+
+    // Every land cell reaches the sea or the map edge.
+    let mut current = cell;
+    for _ in 0..map.points.len() {
+      match hydrology.downstream[current] {
+        Some(next) => current = next,
+        None => break
+      }
+    }
+    assert!(hydrology.downstream[current].is_none());
+  }
+  for (cell, upstream) in upstream.iter().enumerate() {
+    let own = if map.elevation[cell] > 0.0 { 1.0 } else { 0.0 };
+    assert_eq!(hydrology.flux[cell], own + upstream);
+  }
+  assert!(hydrology.rivers.iter().any(|river| *river));
+}
@@ -0,0 +1,120 @@
+//! Landmasses and their coastlines.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use std::collections::HashSet;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::coastline::CoastlineOptions;
+use wasm_planet_generator::map::Point;
+use wasm_planet_generator::mesh::distance;
+use wasm_planet_generator::options::Options;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn landmasses_gather_connected_land() {
+  let map = earth(&Options::default());
+  let mesh = map.mesh();
+
+  assert!(map.landmasses.len() > 1);
+  // Cells tile the map.
+  let total: f64 = (0..map.points.len()).map(|cell| mesh.area(cell)).sum();
+  assert!((total - 400.0 * 300.0).abs() < 1e-6);
+  let mut cells = vec![0; map.landmasses.len()];
+  let mut area = vec![0.0; map.landmasses.len()];
+  let mut coastline = vec![0.0; map.landmasses.len()];
+  for cell in 0..map.points.len() {
+    let Some(id) = map.landmass_ids[cell] else {
+      assert!(map.elevation[cell] <= 0.0);
+      continue;
+    };
+    assert!(map.elevation[cell] > 0.0);
+    cells[id] += 1;
+    area[id] += mesh.area(cell);
+    for side in mesh.sides(cell) {
+      let Some(neighbor) = side.neighbor else { continue };
+      match map.landmass_ids[neighbor] {
+        Some(other) => assert_eq!(other, id),
+        None => coastline[id] += distance(&mesh.corners[side.from], &mesh.corners[side.to])
+      }
+    }
+  }
+
+  for (id, landmass) in map.landmasses.iter().enumerate() {
+    assert_eq!(landmass.id, id);
+    assert_eq!(landmass.cells, cells[id]);
+    assert!((landmass.area - area[id]).abs() < 1e-9);
+    assert!((landmass.coastline - coastline[id]).abs() < 1e-9);
+    assert_eq!(map.landmass_ids[landmass.highest], Some(id));
+    assert_eq!(landmass.highest_elevation, map.elevation[landmass.highest]);
+    let within = |point: &Point| {
+      (landmass.bounds.min.0..=landmass.bounds.max.0).contains(&point.0)
+        && (landmass.bounds.min.1..=landmass.bounds.max.1).contains(&point.1)
+    };
+    assert!(within(&landmass.centroid));
+    for cell in (0..map.points.len()).filter(|cell| map.landmass_ids[*cell] == Some(id)) {
+      assert!(within(&map.points[cell]));
+      assert!(map.elevation[cell] <= landmass.highest_elevation);
+    }
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn coastlines_close_around_landmasses() {
+  let outlined = |smoothing: usize| {
+    let options = Options { coastlines: Some(CoastlineOptions { smoothing }), ..Options::default() };
+    earth(&options)
+  };
+  let map = outlined(0);
+  let mesh = map.mesh();
+  let coastlines = map.coastlines.as_ref().unwrap();
+
+  // Every side of a land cell facing water or the map edge is walked once,
+  // from corner to neighbouring corner, back to where it started.
+  let key = |point: &Point| (point.0.to_bits(), point.1.to_bits());
+  let sides: HashSet<_> = (0..map.points.len())
+    .filter(|cell| map.elevation[*cell] > 0.0)
+    .flat_map(|cell| mesh.sides(cell).iter())
+    .filter(|side| side.neighbor.is_none_or(|neighbor| map.elevation[neighbor] <= 0.0))
+    .map(|side| (key(&mesh.corners[side.from]), key(&mesh.corners[side.to])))
+    .collect();
+  assert_eq!(coastlines.iter().map(|coastline| coastline.points.len()).sum::<usize>(), sides.len());
+  for coastline in coastlines.iter() {
+    for (index, point) in coastline.points.iter().enumerate() {
+      let next = &coastline.points[(index + 1) % coastline.points.len()];
+      assert!(sides.contains(&(key(point), key(next))), "{:?} and {:?} aren't joined by a side", key(point), key(next));
+    }
+  }
+  for landmass in map.landmasses.iter() {
+    assert!(coastlines.iter().any(|coastline| coastline.landmass == landmass.id));
+  }
+
+  // Smoothing cuts the corners of the closed polylines, last to first included.
+  let chaikin = |points: &[Point]| -> Vec<Point> {
+    (0..points.len())
+      .flat_map(|index| {
+        let (point, next) = (&points[index], &points[(index + 1) % points.len()]);
+        vec![
+          Point(point.0 * 0.75 + next.0 * 0.25, point.1 * 0.75 + next.1 * 0.25),
+          Point(point.0 * 0.25 + next.0 * 0.75, point.1 * 0.25 + next.1 * 0.75)
+        ]
+      })
+      .collect()
+  };
+  let smoothed = outlined(2);
+  let smoothed = smoothed.coastlines.as_ref().unwrap();
+  assert_eq!(smoothed.len(), coastlines.len());
+  for (coastline, smooth) in coastlines.iter().zip(smoothed.iter()) {
+    assert_eq!((smooth.landmass, smooth.lake), (coastline.landmass, coastline.lake));
+    let expected = chaikin(&chaikin(&coastline.points));
+    assert_eq!(smooth.points.len(), expected.len());
+    for (point, expected) in smooth.points.iter().zip(expected.iter()) {
+      assert!(distance(point, expected) < 1e-9);
+    }
+  }
+}
@@ -0,0 +1,65 @@
+//! Generated names of features.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use rand::SeedableRng;
+use std::collections::HashSet;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::names::{Culture, NameGenerator, NameOptions};
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::settlements::SettlementOptions;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn features_get_distinct_names() {
+  let options = Options {
+    hydrology: Some(HydrologyOptions::default()),
+    settlements: Some(SettlementOptions::default()),
+    names: Some(NameOptions { culture: Culture::Norse, ..NameOptions::default() }),
+    ..Options::default()
+  };
+  let map = earth(&options);
+  let names = map.names.unwrap();
+
+  assert_eq!(names.landmasses.len(), map.landmasses.len());
+  assert_eq!(names.settlements.len(), map.settlements.unwrap().len());
+  assert!(!names.rivers.is_empty());
+  assert!(names.water_bodies.iter().all(|body| body.cells > 0));
+
+  let mut all: Vec<&String> = names.landmasses.iter()
+    .chain(names.water_bodies.iter().map(|body| &body.name))
+    .chain(names.rivers.iter().map(|river| &river.name))
+    .chain(names.settlements.iter())
+    .collect();
+  assert!(all.iter().all(|name| (4..=10).contains(&name.len()) && name.chars().next().unwrap().is_uppercase()));
+  let count = all.len();
+  all.sort();
+  all.dedup();
+  assert_eq!(all.len(), count);
+
+  // Settlements draw from their own generator, leaving the other names as is.
+  let options = Options { settlements: None, ..options };
+  let unsettled = earth(&options).names.unwrap();
+  assert_eq!(unsettled.landmasses, names.landmasses);
+  assert!(unsettled.settlements.is_empty());
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn short_word_lists_still_give_distinct_names() {
+  let words = vec!["mora".to_string(), "nora".to_string(), "lora".to_string()];
+  let generator = NameGenerator::new(&NameOptions { words: words.clone(), ..NameOptions::default() });
+  let mut random = SeedableRng::seed_from_u64(7);
+  let mut used = HashSet::new();
+  let names: Vec<String> = (0..50).map(|_| generator.generate(&mut random, &mut used)).collect();
+
+  let distinct: HashSet<String> = names.iter().map(|name| name.to_lowercase()).collect();
+  assert_eq!(distinct.len(), names.len());
+  assert!(names.iter().all(|name| !words.contains(&name.to_lowercase())));
+}
@@ -0,0 +1,115 @@
+//! Pathfinding over the cells of a generated map.
+
+extern crate wasm_bindgen_test;
+use std::collections::BTreeMap;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::climate::ClimateOptions;
+use wasm_planet_generator::elevation::Terrain;
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::pathfinding::{CostTable, Movement, find_path, reachable};
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn map() -> Map {
+  let options = Options { terrain: true, ..Options::default() };
+  Map::generate_with("paths".to_string(), 300.0, 200.0, 8.0, 0.5, &options)
+}
+
+// First land cell and last water cell, far apart on the map.
+fn ends(map: &Map) -> (usize, usize) {
+  let land = (0..map.points.len()).find(|cell| map.elevation[*cell] > 0.0).unwrap();
+  let water = (0..map.points.len()).rev().find(|cell| map.elevation[*cell] <= 0.0).unwrap();
+  (land, water)
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn paths_are_as_cheap_as_reachable_costs() {
+  let map = map();
+  let mesh = map.mesh();
+  let mut terrain = BTreeMap::new();
+  terrain.insert(Terrain::Mountain, 4.0);
+  let costs = CostTable { water: Some(2.0), terrain, climb: 50.0, ..CostTable::default() };
+  let (from, to) = ends(&map);
+
+  let path = find_path(&map, from, to, &costs).unwrap();
+  assert_eq!(path.cells.first(), Some(&from));
+  assert_eq!(path.cells.last(), Some(&to));
+  assert!(path.cells.windows(2).all(|pair| mesh.neighbors(pair[0]).contains(&pair[1])));
+
+  let reached = reachable(&map, from, f64::INFINITY, &costs);
+  assert_eq!(reached.len(), map.points.len());
+  assert!(reached.windows(2).all(|pair| pair[0].cost <= pair[1].cost));
+  let cheapest = reached.iter().find(|reached| reached.cell == to).unwrap().cost;
+  assert!((path.cost - cheapest).abs() < 1e-9);
+
+  let budget = path.cost / 2.0;
+  let within = reachable(&map, from, budget, &costs);
+  assert!(within.iter().all(|reached| reached.cost <= budget));
+  assert!(!within.iter().any(|reached| reached.cell == to));
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn movements_can_forbid_cells() {
+  let map = map();
+  let (land, water) = ends(&map);
+  let boat = CostTable { water: Some(1.0), water_only: true, ..CostTable::default() };
+  assert!(find_path(&map, water, land, &boat).is_none());
+  assert!(reachable(&map, water, f64::INFINITY, &boat).iter().all(|reached| map.elevation[reached.cell] <= 0.0));
+
+  let lowlands = |map: &Map, _: usize, to: usize| if map.elevation[to] < 0.3 { Some(1.0) } else { None };
+  let path = find_path(&map, land, water, &lowlands).unwrap();
+  assert!(path.cells.iter().skip(1).all(|cell| map.elevation[*cell] < 0.3));
+  assert_eq!(path.cost, (path.cells.len() - 1) as f64);
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn invalid_costs_are_never_taken() {
+  assert!(serde_json::from_str::<CostTable>(r#"{ "land": -1.0 }"#).is_err());
+  assert!(serde_json::from_str::<CostTable>(r#"{ "water": -0.5 }"#).is_err());
+  assert!(serde_json::from_str::<CostTable>(r#"{ "terrain": { "mountain": -2.0 } }"#).is_err());
+  assert!(serde_json::from_str::<CostTable>(r#"{ "terrain": { "mountain": 2.0 } }"#).is_ok());
+  assert!(serde_json::from_str::<CostTable>(r#"{ "koppen": { "EF": -2.0 } }"#).is_err());
+  assert!(serde_json::from_str::<CostTable>(r#"{ "land": 2.0, "koppen": { "EF": 3.0 } }"#).is_ok());
+
+  let map = map();
+  let (land, water) = ends(&map);
+  let broken = |map: &Map, _: usize, to: usize| match map.elevation[to] {
+    elevation if elevation > 0.5 => Some(-1.0),
+    elevation if elevation > 0.3 => Some(f64::NAN),
+    _ => Some(1.0)
+  };
+  let path = find_path(&map, land, water, &broken).unwrap();
+  assert!(path.cost.is_finite());
+  assert!(path.cells.iter().skip(1).all(|cell| map.elevation[*cell] <= 0.3));
+  assert!(reachable(&map, land, f64::INFINITY, &broken).iter().all(|reached| reached.cost >= 0.0));
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn climate_classes_scale_land_costs() {
+  let options = Options {
+    terrain: true,
+    climate: Some(ClimateOptions { seasons: true, ..ClimateOptions::default() }),
+    ..Options::default()
+  };
+  let map = Map::generate_with("paths".to_string(), 300.0, 200.0, 8.0, 0.5, &options);
+  let mesh = map.mesh();
+  let classes = &map.climate.as_ref().unwrap().seasons.as_ref().unwrap().koppen;
+  let (from, to) = (0..map.points.len())
+    .flat_map(|cell| mesh.neighbors(cell).into_iter().map(move |neighbor| (cell, neighbor)))
+    .find(|(_, to)| map.elevation[*to] > 0.0 && classes[*to].is_some())
+    .unwrap();
+
+  let plain = CostTable::default();
+  let mut koppen = BTreeMap::new();
+  koppen.insert(classes[to].unwrap(), 3.0);
+  let scaled = CostTable { koppen, ..CostTable::default() };
+  let cost = plain.step(&map, from, to).unwrap();
+  assert!((scaled.step(&map, from, to).unwrap() - cost * 3.0).abs() < 1e-9);
+  assert_eq!(scaled.min_rate(), 1.0);
+}
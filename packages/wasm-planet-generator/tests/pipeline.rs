@@ -0,0 +1,138 @@
+//! Caching of the generation pipeline.
+
+extern crate rand;
+extern crate wasm_bindgen_test;
+use rand::{Rng, rngs::StdRng};
+use wasm_bindgen_test::*;
+use wasm_planet_generator::climate::ClimateOptions;
+use wasm_planet_generator::coastline::CoastlineOptions;
+use wasm_planet_generator::elevation::{NoiseLayer, elevate_with_layers};
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::map::{Map, Point};
+use wasm_planet_generator::mesh::{distance, lattice};
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::pipeline::{Parameters, Pipeline, Step};
+use wasm_planet_generator::progress::{Silent, Stage};
+use wasm_planet_generator::stage::{Draft, MapStage};
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn parameters(chaos: f64) -> Parameters {
+  Parameters { seed: "pipeline".to_string(), width: 120.0, height: 90.0, spacing: 6.0, chaos }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn matches_one_off_generation() {
+  let options = Options { hydrology: Some(HydrologyOptions::default()), ..Options::default() };
+  let map = Map::generate_with("pipeline".to_string(), 120.0, 90.0, 6.0, 0.5, &options);
+  let mut pipeline = Pipeline::new();
+  let cached = pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+
+  assert_eq!(cached.elevation, map.elevation);
+  assert_eq!(cached.hydrology.unwrap().rivers, map.hydrology.unwrap().rivers);
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn only_recomputes_affected_steps() {
+  let mut pipeline = Pipeline::new();
+  let mut options = Options::default();
+
+  // Stages that aren't enabled, hydrology among them, aren't steps of the run.
+  let first = pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed(), &[
+    Step::Points, Step::Perturb, Step::Relax, Step::Elevate, Step::Tectonics, Step::SeaLevel, Step::Assemble
+  ]);
+
+  let again = pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert!(pipeline.recomputed().is_empty());
+  // The reused map is timed for this run, where elevating took next to no time.
+  let stages = |map: &Map| map.meta.timings.iter().map(|timing| timing.stage).collect::<Vec<Stage>>();
+  assert_eq!(stages(&again), stages(&first));
+  #[cfg(not(target_arch = "wasm32"))]
+  assert!(again.meta.timings[2].milliseconds < first.meta.timings[2].milliseconds);
+
+  options.coastlines = Some(CoastlineOptions { smoothing: 1 });
+  pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed(), &[Step::Assemble]);
+
+  options.hydrology = Some(HydrologyOptions::default());
+  pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed(), &[Step::Hydrology, Step::Assemble]);
+
+  // Noise layers are evaluated apart from the elevation, which is reused.
+  options.noise_layers = vec![NoiseLayer::RiverPositions];
+  let layered = pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed(), &[Step::NoiseLayers, Step::Assemble]);
+  let elevated = elevate_with_layers(layered.meta.seed_value, &layered.points, 120.0, 90.0, &options.noise_layers, true, &mut |_| true)
+    .unwrap();
+  assert_eq!(layered.noise_layers["riverPositions"], elevated.layers[0]);
+
+  // Stages see the terrain, so classifying it runs them again.
+  options.terrain = true;
+  let classified = pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed(), &[Step::NoiseLayers, Step::Hydrology, Step::Assemble]);
+  assert_eq!(classified.terrain.unwrap().len(), elevated.terrain.len());
+
+  pipeline.generate(&parameters(0.8), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed()[0], Step::Perturb);
+}
+
+// How far apart consecutive points of each column are from the lattice spacing.
+fn unevenness(points: &[Point]) -> f64 {
+  let (_, rows) = lattice(120.0, 90.0, 6.0);
+  (0..points.len())
+    .filter(|index| index % rows + 1 < rows)
+    .map(|index| (distance(&points[index], &points[index + 1]) - 6.0).abs())
+    .sum()
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn relaxation_evens_out_points() {
+  let mut pipeline = Pipeline::new();
+  pipeline.run(Step::Relax, &parameters(1.0), &Options::default(), &mut Silent).unwrap();
+  let perturbed = unevenness(pipeline.points().unwrap());
+
+  let options = Options { relaxation: 3, ..Options::default() };
+  pipeline.run(Step::Relax, &parameters(1.0), &options, &mut Silent).unwrap();
+
+  assert_eq!(pipeline.recomputed(), &[Step::Relax]);
+  assert!(unevenness(pipeline.points().unwrap()) < perturbed);
+}
+
+// Marks random land cells, more likely on high ground.
+struct Ruins;
+
+impl MapStage for Ruins {
+  fn name(&self) -> &str {
+    "ruins"
+  }
+
+  fn run(&self, draft: &mut Draft, random: &mut StdRng) {
+    let ruins = draft.elevation
+      .iter()
+      .map(|value| if *value > 0.0 && random.gen::<f64>() < *value { 1.0 } else { 0.0 })
+      .collect();
+    draft.add_layer("ruins", ruins);
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn runs_custom_stages_after_built_in_ones() {
+  let options = Options { climate: Some(ClimateOptions::default()), ..Options::default() };
+  let mut pipeline = Pipeline::new();
+  pipeline.register(Box::new(Ruins));
+
+  let map = pipeline.generate(&parameters(0.5), &options, &mut Silent).unwrap();
+  assert_eq!(pipeline.recomputed()[6..], [Step::Climate, Step::Custom, Step::Assemble]);
+  assert_eq!(map.layers["ruins"].len(), map.points.len());
+  assert!(map.climate.is_some());
+
+  let mut other = Pipeline::new();
+  other.register(Box::new(Ruins));
+  assert_eq!(other.generate(&parameters(0.5), &options, &mut Silent).unwrap().layers["ruins"], map.layers["ruins"]);
+}
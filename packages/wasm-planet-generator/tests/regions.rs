@@ -0,0 +1,35 @@
+//! Regions grown from their capitals.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::regions::RegionOptions;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn regions_split_land_along_borders() {
+  let options = Options { regions: Some(RegionOptions::default()), ..Options::default() };
+  let map = earth(&options);
+  let regions = map.regions.unwrap();
+
+  assert!(regions.regions.len() > 1);
+  for (region, elevation) in regions.region_ids.iter().zip(map.elevation.iter()) {
+    assert!(region.is_none() || *elevation > 0.0);
+  }
+  for region in regions.regions.iter() {
+    assert_eq!(regions.region_ids[region.capital], Some(region.id));
+    for neighbor in region.neighbors.iter() {
+      assert!(regions.regions[*neighbor].neighbors.contains(&region.id));
+    }
+  }
+  for border in regions.borders.iter() {
+    assert!(regions.regions[border.regions.0].neighbors.contains(&border.regions.1));
+    assert!(border.points.len() > 1);
+  }
+}
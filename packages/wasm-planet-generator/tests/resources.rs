@@ -0,0 +1,67 @@
+//! Resource deposits.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::elevation::Terrain;
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::resources::{Resource, ResourceOptions};
+use wasm_planet_generator::tectonics::TectonicsOptions;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn deposits_follow_geology() {
+  let deposited = |resources: ResourceOptions| {
+    let options = Options {
+      terrain: true,
+      tectonics: Some(TectonicsOptions::default()),
+      resources: Some(resources),
+      ..Options::default()
+    };
+    earth(&options)
+  };
+  let count = |map: &Map, resource: Resource| {
+    map.resources.as_ref().unwrap().iter().flatten().filter(|deposit| deposit.resource == resource).count()
+  };
+
+  let map = deposited(ResourceOptions::default());
+  let terrain = map.terrain.as_ref().unwrap();
+  for (cell, deposits) in map.resources.as_ref().unwrap().iter().enumerate() {
+    for deposit in deposits.iter() {
+      assert!(deposit.richness >= 0.0 && deposit.richness < 1.0);
+      match deposit.resource {
+        Resource::Fish => assert_eq!(terrain[cell], Terrain::Shelf),
+        Resource::Ore | Resource::Timber => assert!(map.elevation[cell] > 0.0),
+        Resource::Oil => assert!(map.elevation[cell] > 0.0 || terrain[cell] == Terrain::Shelf)
+      }
+    }
+  }
+  for resource in [Resource::Ore, Resource::Oil, Resource::Fish, Resource::Timber].iter() {
+    assert!(count(&map, *resource) > 0, "no {:?}", resource);
+  }
+
+  let scarce = deposited(ResourceOptions { ore: 0.05, ..ResourceOptions::default() });
+  assert!(count(&scarce, Resource::Ore) < count(&map, Resource::Ore));
+  assert_eq!(count(&scarce, Resource::Fish), count(&map, Resource::Fish));
+  assert_eq!(deposited(ResourceOptions::default()).resources, map.resources);
+
+  // Without terrain, fish stay off the open ocean the terrain would classify.
+  let options = Options { resources: Some(ResourceOptions::default()), ..Options::default() };
+  let unclassified = earth(&options);
+  let terrain = earth(&Options { terrain: true, ..options }).terrain.unwrap();
+  let fished: Vec<usize> = unclassified.resources.as_ref().unwrap().iter().enumerate()
+    .filter(|(_, deposits)| deposits.iter().any(|deposit| deposit.resource == Resource::Fish))
+    .map(|(cell, _)| cell)
+    .collect();
+  assert!(!fished.is_empty());
+  for cell in fished {
+    assert!(unclassified.elevation[cell] <= 0.0);
+    assert!(terrain[cell] != Terrain::Ocean && terrain[cell] != Terrain::Trench, "fish in the {:?}", terrain[cell]);
+  }
+}
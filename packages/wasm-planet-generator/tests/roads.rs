@@ -0,0 +1,46 @@
+//! Roads between settlements.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use wasm_bindgen_test::*;
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::roads::{RoadClass, RoadOptions};
+use wasm_planet_generator::settlements::SettlementOptions;
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn roads_follow_cell_edges() {
+  let options = Options {
+    terrain: true,
+    hydrology: Some(HydrologyOptions::default()),
+    settlements: Some(SettlementOptions::default()),
+    roads: Some(RoadOptions::default()),
+    ..Options::default()
+  };
+  let map = earth(&options);
+  let mesh = map.mesh();
+  let settlements: Vec<usize> = map.settlements.as_ref().unwrap().iter().map(|settlement| settlement.cell).collect();
+  let roads = map.roads.as_ref().unwrap();
+
+  assert!(roads.iter().any(|road| road.class == RoadClass::Highway));
+  for road in roads.iter() {
+    assert_eq!(road.points.len(), road.cells.len());
+    assert!(road.cells.windows(2).all(|pair| mesh.neighbors(pair[0]).contains(&pair[1])));
+    if road.class == RoadClass::Sea {
+      let landings = road.cells.iter().filter(|cell| map.elevation[**cell] > 0.0);
+      assert!(landings.into_iter().all(|cell| settlements.contains(cell)));
+    } else {
+      assert!(road.cells.iter().all(|cell| map.elevation[*cell] > 0.0));
+    }
+  }
+
+  let options = Options { roads: Some(RoadOptions { sea_routes: false, ..RoadOptions::default() }), ..options };
+  let map = earth(&options);
+  assert!(map.roads.unwrap().iter().all(|road| road.class != RoadClass::Sea));
+}
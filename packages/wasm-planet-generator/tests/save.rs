@@ -0,0 +1,184 @@
+//! Round trips of the binary save format.
+
+extern crate wasm_bindgen_test;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::circulation::CirculationOptions;
+use wasm_planet_generator::climate::ClimateOptions;
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::ice::IceOptions;
+use wasm_planet_generator::map::Map;
+use wasm_planet_generator::names::NameOptions;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::regions::RegionOptions;
+use wasm_planet_generator::resources::ResourceOptions;
+use wasm_planet_generator::roads::RoadOptions;
+use wasm_planet_generator::settlements::SettlementOptions;
+use wasm_planet_generator::save::{self, LoadError, SaveOptions, FORMAT_VERSION};
+use wasm_planet_generator::tectonics::TectonicsOptions;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+fn saved(save_options: &SaveOptions) -> (Map, Vec<u8>) {
+  let options = Options {
+    tectonics: Some(TectonicsOptions::default()),
+    hydrology: Some(HydrologyOptions::default()),
+    climate: Some(ClimateOptions { seasons: true, ..ClimateOptions::default() }),
+    terrain: true,
+    regions: Some(RegionOptions::default()),
+    settlements: Some(SettlementOptions::default()),
+    roads: Some(RoadOptions::default()),
+    circulation: Some(CirculationOptions::default()),
+    ice: Some(IceOptions::default()),
+    resources: Some(ResourceOptions::default()),
+    names: Some(NameOptions::default()),
+    ..Options::default()
+  };
+  let map = Map::generate_with("saved".to_string(), 120.0, 90.0, 6.0, 0.5, &options);
+  let bytes = save::write(&map, save_options);
+  (map, bytes)
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn round_trips_raw_layers() {
+  let (map, bytes) = saved(&SaveOptions::default());
+  let (header, loaded) = save::read(&bytes).unwrap();
+
+  assert_eq!(header.seed, "saved");
+  assert_eq!((header.width, header.height, header.spacing, header.chaos), (120.0, 90.0, 6.0, 0.5));
+  assert_eq!(header.format_version, FORMAT_VERSION);
+  assert_eq!(header.generator_version, env!("CARGO_PKG_VERSION"));
+  assert_eq!(loaded.meta.seed_value, map.meta.seed_value);
+  assert_eq!(loaded.meta.timings.len(), map.meta.timings.len());
+  assert_eq!(loaded.elevation, map.elevation);
+  assert_eq!(loaded.landmasses.len(), map.landmasses.len());
+  assert_eq!(loaded.tectonics.unwrap().plate_ids, map.tectonics.unwrap().plate_ids);
+  assert_eq!(loaded.hydrology.unwrap().rivers, map.hydrology.unwrap().rivers);
+  assert_eq!(loaded.terrain, map.terrain);
+  let (loaded_ice, ice) = (loaded.ice.unwrap(), map.ice.unwrap());
+  assert_eq!(loaded_ice.cells, ice.cells);
+  assert_eq!(loaded_ice.glaciers, ice.glaciers);
+  assert_eq!(loaded.resources, map.resources);
+  assert_eq!(loaded.names.unwrap().settlements, map.names.unwrap().settlements);
+  assert_eq!(loaded.settlements.unwrap().len(), map.settlements.unwrap().len());
+  let (loaded_roads, roads) = (loaded.roads.unwrap(), map.roads.unwrap());
+  assert_eq!(loaded_roads.len(), roads.len());
+  assert!(loaded_roads.iter().zip(roads.iter()).all(|(a, b)| a.class == b.class && a.cells == b.cells));
+  assert_eq!(loaded.regions.unwrap().region_ids, map.regions.unwrap().region_ids);
+  let (loaded_circulation, circulation) = (loaded.circulation.unwrap(), map.circulation.unwrap());
+  assert!(loaded_circulation.currents.iter().zip(circulation.currents.iter()).all(|(a, b)| a.0 == b.0 && a.1 == b.1));
+  let (loaded_climate, climate) = (loaded.climate.unwrap(), map.climate.unwrap());
+  assert_eq!(loaded_climate.temperature, climate.temperature);
+  assert_eq!(loaded_climate.seasons.unwrap().koppen, climate.seasons.unwrap().koppen);
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn keeps_renamed_features() {
+  let (mut map, _) = saved(&SaveOptions::default());
+  let names = map.names.as_mut().unwrap();
+  names.settlements[0] = "Renamed".to_string();
+  names.landmasses[0] = "Somewhere".to_string();
+  let (_, loaded) = save::read(&save::write(&map, &SaveOptions::default())).unwrap();
+
+  let loaded = loaded.names.unwrap();
+  assert_eq!(loaded.settlements[0], "Renamed");
+  assert_eq!(loaded.landmasses[0], "Somewhere");
+  assert_eq!(loaded.settlements, map.names.unwrap().settlements);
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn quantized_saves_are_smaller() {
+  let (map, raw) = saved(&SaveOptions::default());
+  let (_, quantized) = saved(&SaveOptions { quantize: true });
+  let (_, loaded) = save::read(&quantized).unwrap();
+
+  assert!(quantized.len() < raw.len());
+  for (expected, actual) in map.elevation.iter().zip(loaded.elevation.iter()) {
+    assert!((expected - actual).abs() < 1e-6);
+  }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn rejects_newer_and_broken_saves() {
+  let (_, mut bytes) = saved(&SaveOptions::default());
+
+  assert_eq!(save::read(&bytes[..bytes.len() - 1]).err(), Some(LoadError::Truncated));
+  assert_eq!(save::read(b"nope").err(), Some(LoadError::InvalidMagic));
+
+  bytes[4..6].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+  assert_eq!(
+    save::read(&bytes).err(),
+    Some(LoadError::IncompatibleVersion { found: FORMAT_VERSION + 1, supported: FORMAT_VERSION })
+  );
+}
+
+// Replaces the payload of the chunk tagged `tag`, fixing its length.
+fn rewrite_chunk(bytes: &[u8], tag: u8, rewrite: impl Fn(&mut Vec<u8>)) -> Vec<u8> {
+  let u32_at = |offset: usize| u32::from_le_bytes([bytes[offset], bytes[offset + 1], bytes[offset + 2], bytes[offset + 3]]) as usize;
+  // Magic and format version, two strings, four floats and the options.
+  let mut offset = 6;
+  for _ in 0..2 {
+    offset += 4 + u32_at(offset);
+  }
+  offset += 4 * 8;
+  offset += 4 + u32_at(offset);
+
+  let mut rewritten = bytes[..offset].to_vec();
+  while offset < bytes.len() {
+    let length = u32_at(offset + 1);
+    let mut payload = bytes[offset + 5..offset + 5 + length].to_vec();
+    if bytes[offset] == tag {
+      rewrite(&mut payload);
+    }
+    rewritten.push(bytes[offset]);
+    rewritten.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    rewritten.extend_from_slice(&payload);
+    offset += 5 + length;
+  }
+  rewritten
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn rejects_layers_not_matching_the_map() {
+  const POINTS_CHUNK: u8 = 1;
+  const HYDROLOGY_CHUNK: u8 = 4;
+  const CIRCULATION_CHUNK: u8 = 12;
+  let (map, bytes) = saved(&SaveOptions::default());
+  let cells = map.points.len();
+  let invalid = |bytes: &[u8]| matches!(save::read(bytes), Err(LoadError::InvalidData(_)));
+
+  // Drops the last river flags.
+  let truncated = rewrite_chunk(&bytes, HYDROLOGY_CHUNK, |payload| {
+    let count = payload.len() - cells - 4;
+    payload[count..count + 4].copy_from_slice(&((cells - 10) as u32).to_le_bytes());
+    payload.truncate(payload.len() - 10);
+  });
+  assert!(invalid(&truncated));
+
+  // Sends the first cell downstream of the map.
+  let outside = rewrite_chunk(&bytes, HYDROLOGY_CHUNK, |payload| {
+    payload[4..8].copy_from_slice(&(cells as u32).to_le_bytes());
+  });
+  assert!(invalid(&outside));
+
+  // Drops the last coordinate.
+  let odd = rewrite_chunk(&bytes, POINTS_CHUNK, |payload| {
+    let length = payload.len();
+    payload.truncate(length - 8);
+    payload[1..5].copy_from_slice(&((cells * 2 - 1) as u32).to_le_bytes());
+  });
+  assert!(invalid(&odd));
+
+  // Drops the last wind coordinate.
+  let odd_wind = rewrite_chunk(&bytes, CIRCULATION_CHUNK, |payload| {
+    let end = 5 + cells * 2 * 8;
+    payload.drain(end - 8..end);
+    payload[1..5].copy_from_slice(&((cells * 2 - 1) as u32).to_le_bytes());
+  });
+  assert!(invalid(&odd_wind));
+}
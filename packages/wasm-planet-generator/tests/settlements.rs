@@ -0,0 +1,48 @@
+//! Settlement placement.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use std::collections::HashSet;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::climate::ClimateOptions;
+use wasm_planet_generator::hydrology::HydrologyOptions;
+use wasm_planet_generator::mesh::distance;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::regions::RegionOptions;
+use wasm_planet_generator::settlements::{SettlementOptions, Tier};
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn settlements_keep_their_distances() {
+  let options = Options {
+    hydrology: Some(HydrologyOptions::default()),
+    climate: Some(ClimateOptions::default()),
+    regions: Some(RegionOptions::default()),
+    settlements: Some(SettlementOptions::default()),
+    ..Options::default()
+  };
+  let map = earth(&options);
+  let settlements = map.settlements.unwrap();
+  let capitals: Vec<usize> = map.regions.unwrap().regions.iter().map(|region| region.capital).collect();
+
+  assert!(settlements.len() > capitals.len());
+  assert!(settlements.windows(2).all(|pair| pair[0].tier <= pair[1].tier));
+  for (index, settlement) in settlements.iter().enumerate() {
+    assert!(map.elevation[settlement.cell] > 0.0);
+    assert_eq!(settlement.tier == Tier::Capital, capitals.contains(&settlement.cell));
+    for other in settlements.iter().skip(index + 1).filter(|other| other.tier != Tier::Capital) {
+      assert!(distance(&settlement.position, &other.position) >= SettlementOptions::default().min_spacing);
+    }
+  }
+
+  // Without spacing, capitals still aren't settled twice.
+  let options = Options { settlements: Some(SettlementOptions { min_spacing: 0.0, ..SettlementOptions::default() }), ..options };
+  let settlements = earth(&options).settlements.unwrap();
+  let cells: HashSet<usize> = settlements.iter().map(|settlement| settlement.cell).collect();
+  assert_eq!(cells.len(), settlements.len());
+}
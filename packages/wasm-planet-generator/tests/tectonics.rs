@@ -0,0 +1,54 @@
+//! Tectonic plates and their boundaries.
+
+extern crate wasm_bindgen_test;
+mod common;
+
+use std::collections::HashSet;
+use wasm_bindgen_test::*;
+use wasm_planet_generator::options::Options;
+use wasm_planet_generator::tectonics::{Boundary, PlateKind, TectonicsOptions};
+use common::earth;
+
+#[cfg(target_arch = "wasm32")]
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+#[wasm_bindgen_test]
+fn plates_split_the_map_along_boundaries() {
+  let options = Options { tectonics: Some(TectonicsOptions::default()), ..Options::default() };
+  let map = earth(&options);
+  let mesh = map.mesh();
+  let tectonics = map.tectonics.as_ref().unwrap();
+
+  assert_eq!(tectonics.plates.len(), TectonicsOptions::default().plates);
+  assert_eq!(tectonics.plate_ids.len(), map.points.len());
+  assert!(tectonics.plate_ids.iter().all(|id| *id < tectonics.plates.len()));
+  for (id, plate) in tectonics.plates.iter().enumerate() {
+    assert_eq!(tectonics.plate_ids[plate.origin], id);
+    // Plates grow from their origin, so they are all in one piece.
+    let mut reached = vec![plate.origin];
+    let mut seen = HashSet::new();
+    seen.insert(plate.origin);
+    while let Some(cell) = reached.pop() {
+      for neighbor in mesh.neighbors(cell) {
+        if tectonics.plate_ids[neighbor] == id && seen.insert(neighbor) {
+          reached.push(neighbor);
+        }
+      }
+    }
+    assert_eq!(seen.len(), tectonics.plate_ids.iter().filter(|other| **other == id).count());
+  }
+  for cell in 0..map.points.len() {
+    let edge = mesh.neighbors(cell).iter().any(|neighbor| tectonics.plate_ids[*neighbor] != tectonics.plate_ids[cell]);
+    assert_eq!(tectonics.boundaries[cell] != Boundary::None, edge);
+    assert!(tectonics.uplift[cell].abs() <= 0.6);
+  }
+  assert!(tectonics.boundaries.iter().any(|boundary| *boundary == Boundary::Convergent || *boundary == Boundary::Subduction));
+  assert!(tectonics.uplift.iter().any(|uplift| *uplift > 0.0));
+
+  let options = Options { tectonics: Some(TectonicsOptions { plates: 3, oceanic_ratio: 0.0, ..TectonicsOptions::default() }), ..options };
+  let tectonics = earth(&options).tectonics.unwrap();
+  assert_eq!(tectonics.plates.len(), 3);
+  assert!(tectonics.plates.iter().all(|plate| plate.kind == PlateKind::Continental));
+  assert!(tectonics.boundaries.iter().all(|boundary| *boundary != Boundary::Subduction));
+}
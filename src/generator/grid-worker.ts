@@ -9,7 +9,12 @@ registerPromiseWorker(options => {
       options.width,
       options.height,
       options.space,
-      options.chaos
+      options.chaos,
+      options,
+      // Progress is posted besides promise-worker replies, which it ignores.
+      (stage: string, done: number) => {
+        ;(self as unknown as Worker).postMessage({ type: 'progress', stage, done })
+      }
     )
   )
 })
@@ -7,16 +7,21 @@ import { Grid, GenerateOptions } from './interfaces'
 
 const generator = import('wasm-planet-generator')
 
+// Reuses the stages whose inputs didn't change since the previous generation.
+const pipeline = generator.then(wasm => new wasm.Pipeline())
+
 export const generateGrid = async (options: GenerateOptions): Promise<Grid> => {
   const start = window.performance.now()
   // const grid = await worker.postMessage(options).then(grid => grid as Grid)
-  const grid = await generator.then(wasm => {
-    return wasm.generateGrid(
+  const grid = await pipeline.then(pipeline => {
+    return pipeline.generate(
       options.seed,
       options.width,
       options.height,
       options.space,
-      options.chaos
+      options.chaos,
+      options,
+      options.onProgress
     )
   })
   const end = window.performance.now()
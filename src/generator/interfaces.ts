@@ -1,26 +1,332 @@
 export type Point = [number, number]
 
+export type PlateKind = 'oceanic' | 'continental'
+
+export type Boundary =
+  | 'none'
+  | 'convergent'
+  | 'subduction'
+  | 'divergent'
+  | 'transform'
+
+export interface Tectonics {
+  plates: { kind: PlateKind; origin: number; velocity: [number, number] }[]
+  plate_ids: number[]
+  boundaries: Boundary[]
+  uplift: number[]
+}
+
+export type Terrain =
+  | 'trench'
+  | 'ocean'
+  | 'shelf'
+  | 'plains'
+  | 'hill'
+  | 'mountain'
+  | 'badlands'
+
+export interface Landmass {
+  id: number
+  cells: number
+  area: number
+  coastline: number
+  bounds: { min: Point; max: Point }
+  highest: number
+  highest_elevation: number
+  centroid: Point
+}
+
+export interface Coastline {
+  landmass: number
+  lake: boolean
+  points: Point[]
+}
+
+export interface Hydrology {
+  // null for water cells and for land cells draining off the map border.
+  downstream: (number | null)[]
+  flux: number[]
+  rivers: boolean[]
+}
+
+// Per-cell vectors, x pointing east and y pointing south, at most 1 long.
+export interface Circulation {
+  wind: Point[]
+  currents: Point[]
+}
+
+// prettier-ignore
+export type Koppen =
+  | 'Af' | 'Am' | 'Aw' | 'As'
+  | 'BWh' | 'BWk' | 'BSh' | 'BSk'
+  | 'Csa' | 'Csb' | 'Csc' | 'Cwa' | 'Cwb' | 'Cwc' | 'Cfa' | 'Cfb' | 'Cfc'
+  | 'Dsa' | 'Dsb' | 'Dsc' | 'Dsd' | 'Dwa' | 'Dwb' | 'Dwc' | 'Dwd' | 'Dfa' | 'Dfb' | 'Dfc' | 'Dfd'
+  | 'ET' | 'EF'
+
+// Monthly series start in January.
+export interface Seasons {
+  temperature: number[][]
+  precipitation: number[][]
+  growing_months: number[]
+  snow_months: number[]
+  koppen: (Koppen | null)[]
+}
+
+export interface Climate {
+  temperature: number[]
+  moisture: number[]
+  seasons?: Seasons
+}
+
+export interface Ice {
+  cells: ('sheet' | 'sea' | 'glacier' | null)[]
+  // Cells of every glacier, from its source down to its tongue.
+  glaciers: number[][]
+}
+
+export interface Deposit {
+  resource: 'ore' | 'oil' | 'fish' | 'timber'
+  // From 0 to 1.
+  richness: number
+}
+
+// Vectors have x pointing east, y south and z up.
+export interface Slope {
+  // Degrees from the horizontal.
+  slope: number[]
+  // Downhill direction, in degrees clockwise from north, null when flat.
+  aspect: (number | null)[]
+  normal: [number, number, number][]
+}
+
+export interface Regions {
+  region_ids: (number | null)[]
+  regions: { id: number; capital: number; cells: number; neighbors: number[] }[]
+  borders: { regions: [number, number]; points: Point[] }[]
+}
+
+export interface Settlement {
+  cell: number
+  position: Point
+  tier: 'capital' | 'city' | 'town' | 'village'
+  score: number
+}
+
+export interface Road {
+  class: 'highway' | 'road' | 'track' | 'sea'
+  cells: number[]
+  points: Point[]
+}
+
+export type Culture = 'latin' | 'norse' | 'elvish'
+
+export interface NameOptions {
+  culture?: Culture
+  words?: string[]
+  order?: number
+  min_length?: number
+  max_length?: number
+}
+
+export interface Names {
+  landmasses: string[]
+  water_body_ids: (number | null)[]
+  water_bodies: { id: number; cells: number; name: string }[]
+  rivers: { mouth: number; cells: number; name: string }[]
+  settlements: string[]
+}
+
+export interface CostTable {
+  land?: number
+  water?: number | null
+  terrain?: Partial<Record<Terrain, number>>
+  koppen?: Partial<Record<Koppen, number>>
+  climb?: number
+  max_elevation?: number | null
+  water_only?: boolean
+}
+
+// Cost of a step between two neighbouring cells, null when it can't be taken.
+// Throwing, or returning a negative or non-finite cost, makes the search throw.
+export type StepCost = (from: number, to: number) => number | null | undefined
+
+export interface Path {
+  cells: number[]
+  cost: number
+}
+
+export interface Reached {
+  cell: number
+  cost: number
+}
+
+export interface DistanceFields {
+  coast: number[]
+  river?: number[]
+  edge: number[]
+}
+
+export interface ElevationParameters {
+  continent_frequency: number
+  continent_lacunarity: number
+  mountain_lacunarity: number
+  hills_lacunarity: number
+  plains_lacunarity: number
+  badlands_lacunarity: number
+  mountains_twist: number
+  hills_twist: number
+  badlands_twist: number
+  sea_level: number
+  shelf_level: number
+  mountains_amount: number
+  hills_amount: number
+  badlands_amount: number
+  terrain_offset: number
+  mountain_glaciation: number
+  continent_height_scale: number
+  river_depth: number
+}
+
+export interface Meta {
+  generator_version: string
+  seed: string
+  // Decimal string, as the numeric seed doesn't fit in a number.
+  seed_value: string
+  width: number
+  height: number
+  spacing: number
+  chaos: number
+  options: Omit<GenerateOptions, 'seed' | 'width' | 'height' | 'space' | 'chaos' | 'onProgress'>
+  elevation: ElevationParameters
+  timings: { stage: GenerationStage; milliseconds: number }[]
+}
+
 export interface Grid {
+  meta: Meta
   points: Point[]
   elevation: number[]
-  triangulation: {
-    triangles: number[]
-    halfedges: number[]
-    hull: number[]
-  }
+  land_ratio: number
+  landmass_ids: (number | null)[]
+  landmasses: Landmass[]
+  coastlines?: Coastline[]
+  hydrology?: Hydrology
+  circulation?: Circulation
+  climate?: Climate
+  ice?: Ice
+  // Deposits of every cell.
+  resources?: Deposit[][]
+  regions?: Regions
+  settlements?: Settlement[]
+  roads?: Road[]
+  names?: Names
+  // Layers added by custom stages, by name.
+  layers?: Record<string, number[]>
+  slope?: Slope
+  distances?: DistanceFields
+  tectonics?: Tectonics
+  terrain?: Terrain[]
+  noise_layers?: Partial<Record<NoiseLayer, number[]>>
+  // Voronoi cells of the points, clipped to the map, as corner indices in
+  // order around every cell.
   subdivision: {
     corners: Point[]
-    vectors: [number, number][]
     cells: number[][]
   }
 }
 
+// Intermediate modules of the elevation noise graph.
+export type NoiseLayer =
+  | 'baseContinentDef'
+  | 'continentDef'
+  | 'terrainTypeDef'
+  | 'mountainBaseDef'
+  | 'mountainousHigh'
+  | 'mountainousLow'
+  | 'mountainousTerrain'
+  | 'hillyTerrain'
+  | 'plainsTerrain'
+  | 'badlandsSand'
+  | 'badlandsCliffs'
+  | 'badlandsTerrain'
+  | 'riverPositions'
+  | 'scaledMountainousTerrain'
+  | 'scaledHillyTerrain'
+  | 'scaledPlainsTerrain'
+  | 'scaledBadlandsTerrain'
+  | 'continentalShelf'
+  | 'baseContinentElev'
+  | 'continentsWithPlains'
+  | 'continentsWithHills'
+  | 'continentsWithMountains'
+  | 'continentsWithBadlands'
+  | 'continentsWithRivers'
+
+export type GenerationStage =
+  | 'points'
+  | 'perturbation'
+  | 'elevation'
+  | 'post_processing'
+
 export interface GenerateOptions {
   seed: string
   width: number
   height: number
   space: number
   chaos: number
+  relaxation?: number
+  noise_layers?: NoiseLayer[]
+  terrain?: boolean
+  tectonics?: TectonicsOptions
+  target_land_ratio?: number
+  coastlines?: { smoothing?: number }
+  // Degrees Celsius added to every temperature, for the climate and ice.
+  temperature_offset?: number
+  hydrology?: { river_threshold?: number }
+  circulation?: { terrain_deflection?: number; current_range?: number }
+  climate?: {
+    equator_temperature?: number
+    pole_temperature?: number
+    lapse_rate?: number
+    moisture_range?: number
+    seasons?: boolean
+    axial_tilt?: number
+    seasonal_range?: number
+    max_precipitation?: number
+  }
+  ice?: {
+    sheet_temperature?: number
+    sea_temperature?: number
+    glacier_temperature?: number
+    glacier_elevation?: number
+    melt_temperature?: number
+  }
+  // Probability for a cell fit for a resource to hold a deposit of it.
+  resources?: { ore?: number; oil?: number; fish?: number; timber?: number }
+  regions?: { count?: number; elevation_cost?: number; river_cost?: number }
+  settlements?: { count?: number; min_spacing?: number; city_ratio?: number; town_ratio?: number }
+  roads?: {
+    slope_cost?: number
+    terrain_cost?: number
+    river_cost?: number
+    reuse_cost?: number
+    sea_routes?: boolean
+    sea_cost?: number
+    port_cost?: number
+  }
+  names?: NameOptions
+  // Height, in map units, of an elevation of 1.
+  slope?: { exaggeration?: number }
+  distances?: boolean
+  // Returning false cancels the generation, which then rejects.
+  onProgress?: (stage: GenerationStage, done: number) => boolean | void
+}
+
+export interface TectonicsOptions {
+  plates?: number
+  oceanic_ratio?: number
+  blend?: number
+  uplift?: number
+  boundary_width?: number
 }
 
 export interface Cell {